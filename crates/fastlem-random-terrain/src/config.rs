@@ -2,7 +2,13 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-#[derive(Debug)]
+use std::f64::consts::FRAC_PI_2;
+
+use fastlem::models::surface::sites::Site2D;
+
+use crate::error::TerrainGenError;
+
+#[derive(Clone, Debug)]
 pub struct Config {
     /// Seed of the noise generator.
     pub seed: u32,
@@ -23,6 +29,15 @@ pub struct Config {
     /// (advanced) Approximate ratio of the land area (0.0-1.0).
     pub land_ratio: f64,
 
+    /// (advanced) Centers around which the land is concentrated.
+    /// If empty, the land is placed by the noise alone.
+    pub landmass_centers: Vec<Site2D>,
+
+    /// (advanced) Radius of the landmasses around their centers.
+    /// Land is more likely than usual within about 70% of the radius, and
+    /// unlikely beyond the radius.
+    pub landmass_radius: f64,
+
     /// (advanced) If true, the edge points of the terrain are always outlet and
     /// its elevation is fixed to 0.
     pub convex_hull_is_always_outlet: bool,
@@ -40,8 +55,44 @@ impl Default for Config {
             erodibility_distribution_power: 4.0,
             fault_scale: 35.0,
             land_ratio: 0.6,
+            landmass_centers: vec![],
+            landmass_radius: f64::INFINITY,
             convex_hull_is_always_outlet: false,
             global_max_slope: 1.57,
         }
     }
 }
+
+impl Config {
+    /// Checks that each field is within its valid range.
+    pub fn validate(&self) -> Result<(), TerrainGenError> {
+        let invalid = |field, expected| Err(TerrainGenError::InvalidConfig { field, expected });
+
+        if self.particle_num == 0 {
+            return invalid("particle_num", "greater than 0");
+        }
+        if !self.erodibility_distribution_power.is_finite() {
+            return invalid("erodibility_distribution_power", "finite");
+        }
+        if !(self.fault_scale.is_finite() && self.fault_scale >= 0.0) {
+            return invalid("fault_scale", "finite and not negative");
+        }
+        if !(0.0..=1.0).contains(&self.land_ratio) {
+            return invalid("land_ratio", "within 0.0-1.0");
+        }
+        if !self
+            .landmass_centers
+            .iter()
+            .all(|center| center.x.is_finite() && center.y.is_finite())
+        {
+            return invalid("landmass_centers", "finite");
+        }
+        if self.landmass_radius.is_nan() || self.landmass_radius <= 0.0 {
+            return invalid("landmass_radius", "greater than 0");
+        }
+        if !(self.global_max_slope > 0.0 && self.global_max_slope <= FRAC_PI_2) {
+            return invalid("global_max_slope", "within 0.0-Pi/2, excluding 0.0");
+        }
+        Ok(())
+    }
+}
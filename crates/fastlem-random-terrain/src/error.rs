@@ -0,0 +1,62 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::error::Error;
+use std::fmt;
+
+use fastlem::lem::generator::GenerationError;
+use fastlem::models::surface::builder::ModelBuilderError;
+
+#[derive(Debug)]
+pub enum TerrainGenError {
+    /// A field of the config is out of its valid range.
+    InvalidConfig {
+        field: &'static str,
+        expected: &'static str,
+    },
+    /// The bounds are empty, or not finite.
+    InvalidBounds,
+    /// The model of the terrain network could not be built.
+    ModelBuilder(ModelBuilderError),
+    /// There is no site which could be an outlet.
+    NoOutlet,
+    /// The landscape evolution model could not be run.
+    Generation(GenerationError),
+}
+
+impl fmt::Display for TerrainGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidConfig { field, expected } => {
+                write!(f, "invalid config: `{field}` should be {expected}")
+            },
+            Self::InvalidBounds => f.write_str("invalid bounds"),
+            Self::ModelBuilder(_) => f.write_str("failed to build the terrain model"),
+            Self::NoOutlet => f.write_str("no outlet for the terrain"),
+            Self::Generation(_) => f.write_str("failed to generate the terrain"),
+        }
+    }
+}
+
+impl Error for TerrainGenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::ModelBuilder(err) => Some(err),
+            Self::Generation(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ModelBuilderError> for TerrainGenError {
+    fn from(err: ModelBuilderError) -> Self {
+        Self::ModelBuilder(err)
+    }
+}
+
+impl From<GenerationError> for TerrainGenError {
+    fn from(err: GenerationError) -> Self {
+        Self::Generation(err)
+    }
+}
@@ -2,10 +2,15 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::fmt;
+use std::mem;
+use std::task::Poll;
+
 use fastlem::core::parameters::TopographicalParameters;
-use fastlem::core::traits::Model as _;
+use fastlem::core::traits::{Model as _, Site as _};
 use fastlem::lem::generator::TerrainGenerator;
 use fastlem::models::surface::builder::TerrainModel2DBulider;
+use fastlem::models::surface::model::TerrainModel2D;
 use fastlem::models::surface::sites::Site2D;
 use fastlem::models::surface::terrain::Terrain2D;
 use log::debug;
@@ -13,14 +18,249 @@ use noise::{NoiseFn as _, Perlin};
 use terrain_graph::edge_attributed_undirected::EdgeAttributedUndirectedGraph;
 
 use crate::config::Config;
+use crate::error::TerrainGenError;
 use crate::math::inversed_perlin_noise_curve;
 
+/// The number of iterations of Lloyd's algorithm for relaxing the sites.
+const RELAXATION_ITERATIONS: usize = 10;
+
+/// A stage of the terrain generation.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Stage {
+    /// Placing the sites randomly.
+    PlacingSites,
+    /// Relaxing the sites, so that they are approximately evenly spaced.
+    ///
+    /// Each iteration of the relaxation is run as its own step.
+    RelaxingSites,
+    /// Building the model of the terrain network.
+    BuildingModel,
+    /// Distributing the topographical parameters to the sites.
+    DistributingParameters,
+    /// Running the landscape evolution model.
+    ///
+    /// The model is run to completion in a single step, as `fastlem` does not
+    /// allow it to be suspended part way. Where the steps are run on the main
+    /// thread, such as on wasm, this step blocks for its whole duration.
+    Generating,
+}
+
+/// Generates the terrain one step at a time, so that the progress can be
+/// reported in between the steps.
+///
+/// Each stage is run as a single step, except for
+/// [`RelaxingSites`](Stage::RelaxingSites), which is run as one step per
+/// iteration.
+///
+/// The generation can be cancelled by dropping it before the last stage has
+/// been run.
+pub struct TerrainGeneration {
+    config: Config,
+    bound_min: Site2D,
+    bound_max: Site2D,
+    bound_range: Site2D,
+    state: State,
+}
+
+enum State {
+    PlacingSites,
+    /// The sites along with the number of relaxation iterations which have
+    /// been run.
+    RelaxingSites(TerrainModel2DBulider, usize),
+    BuildingModel(TerrainModel2DBulider),
+    DistributingParameters(TerrainModel2D),
+    Generating(TerrainModel2D, Vec<TopographicalParameters>),
+    Done,
+}
+
+impl Stage {
+    /// All the stages, in the order in which they are run.
+    pub const ALL: [Self; 5] = [
+        Self::PlacingSites,
+        Self::RelaxingSites,
+        Self::BuildingModel,
+        Self::DistributingParameters,
+        Self::Generating,
+    ];
+
+    /// Returns the expected cost of the stage, as a share of the whole terrain
+    /// generation.
+    ///
+    /// The costs are rough estimates, which add up to 100. Running the
+    /// landscape evolution model takes most of the time.
+    const fn cost(&self) -> u32 {
+        match self {
+            Self::PlacingSites => 1,
+            Self::RelaxingSites => 20,
+            Self::BuildingModel => 5,
+            Self::DistributingParameters => 4,
+            Self::Generating => 70,
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PlacingSites => "placing sites",
+            Self::RelaxingSites => "relaxing sites",
+            Self::BuildingModel => "building model",
+            Self::DistributingParameters => "distributing parameters",
+            Self::Generating => "generating",
+        })
+    }
+}
+
+/// Generates the terrain, running all the stages to completion.
 pub fn generate_terrain(
     config: &Config,
     bound_min: Site2D,
     bound_max: Site2D,
     bound_range: Site2D,
-) -> Terrain2D {
+) -> Result<Terrain2D, TerrainGenError> {
+    let mut generation = TerrainGeneration::new(config.clone(), bound_min, bound_max, bound_range)?;
+    loop {
+        if let Poll::Ready(result) = generation.step() {
+            return result;
+        }
+    }
+}
+
+impl TerrainGeneration {
+    /// Returns an error if the config or the bounds are invalid.
+    pub fn new(
+        config: Config,
+        bound_min: Site2D,
+        bound_max: Site2D,
+        bound_range: Site2D,
+    ) -> Result<Self, TerrainGenError> {
+        config.validate()?;
+        let is_valid_range =
+            |min: f64, max: f64, range: f64| min < max && (max - min).is_finite() && range > 0.0;
+        if !(is_valid_range(bound_min.x, bound_max.x, bound_range.x)
+            && is_valid_range(bound_min.y, bound_max.y, bound_range.y))
+        {
+            return Err(TerrainGenError::InvalidBounds);
+        }
+
+        Ok(Self {
+            config,
+            bound_min,
+            bound_max,
+            bound_range,
+            state: State::PlacingSites,
+        })
+    }
+
+    /// Returns the stage which is run by the next call to
+    /// [`step`](Self::step), or `None` if the terrain has been generated.
+    pub fn stage(&self) -> Option<Stage> {
+        match self.state {
+            State::PlacingSites => Some(Stage::PlacingSites),
+            State::RelaxingSites(..) => Some(Stage::RelaxingSites),
+            State::BuildingModel(_) => Some(Stage::BuildingModel),
+            State::DistributingParameters(_) => Some(Stage::DistributingParameters),
+            State::Generating(..) => Some(Stage::Generating),
+            State::Done => None,
+        }
+    }
+
+    /// Returns the fraction of the work which has been done (0.0-1.0).
+    ///
+    /// The stages are weighted by their expected cost, so that the progress
+    /// does not jump ahead of the time spent.
+    pub fn progress(&self) -> f64 {
+        let total_cost: u32 = Stage::ALL.iter().map(Stage::cost).sum();
+        self.stage().map_or(1.0, |stage| {
+            let completed_cost = f64::from(
+                Stage::ALL
+                    .iter()
+                    .take_while(|&&s| s != stage)
+                    .map(Stage::cost)
+                    .sum::<u32>(),
+            ) + match self.state {
+                State::RelaxingSites(_, iterations) => {
+                    f64::from(stage.cost()) * iterations as f64 / RELAXATION_ITERATIONS as f64
+                },
+                _ => 0.0,
+            };
+            completed_cost / f64::from(total_cost)
+        })
+    }
+
+    /// Runs the next step.
+    ///
+    /// Returns the terrain once the last step has been run, or an error if any
+    /// step has failed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the terrain has already been generated, or if a stage has
+    /// already failed.
+    pub fn step(&mut self) -> Poll<Result<Terrain2D, TerrainGenError>> {
+        if let Some(stage) = self.stage() {
+            debug!("{stage}...");
+        }
+
+        self.state = match mem::replace(&mut self.state, State::Done) {
+            State::PlacingSites => State::RelaxingSites(
+                TerrainModel2DBulider::from_random_sites(
+                    self.config.particle_num,
+                    self.bound_min,
+                    self.bound_max,
+                ),
+                0,
+            ),
+            State::RelaxingSites(builder, iterations) => match builder.relaxate_sites(1) {
+                Ok(builder) if iterations + 1 < RELAXATION_ITERATIONS => {
+                    State::RelaxingSites(builder, iterations + 1)
+                },
+                Ok(builder) => State::BuildingModel(builder),
+                Err(err) => return Poll::Ready(Err(err.into())),
+            },
+            State::BuildingModel(builder) => {
+                match builder
+                    .add_edge_sites(None, None)
+                    .and_then(|builder| builder.build())
+                {
+                    Ok(model) => State::DistributingParameters(model),
+                    Err(err) => return Poll::Ready(Err(err.into())),
+                }
+            },
+            State::DistributingParameters(model) => {
+                let Some(parameters) = distribute_parameters(
+                    &self.config,
+                    self.bound_min,
+                    self.bound_max,
+                    self.bound_range,
+                    &model,
+                ) else {
+                    return Poll::Ready(Err(TerrainGenError::NoOutlet));
+                };
+                State::Generating(model, parameters)
+            },
+            State::Generating(model, parameters) => {
+                let result = TerrainGenerator::default()
+                    .set_model(model)
+                    .set_parameters(parameters)
+                    .generate()
+                    .map_err(TerrainGenError::from);
+                return Poll::Ready(result);
+            },
+            State::Done => panic!("the terrain generation should not have been finished already"),
+        };
+        Poll::Pending
+    }
+}
+
+/// Returns `None` if there is no site which could be an outlet.
+fn distribute_parameters(
+    config: &Config,
+    bound_min: Site2D,
+    bound_max: Site2D,
+    bound_range: Site2D,
+    model: &TerrainModel2D,
+) -> Option<Vec<TopographicalParameters>> {
     // Seed of the noise generator.
     // You can generate various terrains by changing the seed.
     let seed = config.seed;
@@ -28,18 +268,6 @@ pub fn generate_terrain(
     // Noise generator
     let perlin = Perlin::new(seed);
 
-    let num = config.particle_num;
-
-    debug!("creating a model...");
-
-    let model = TerrainModel2DBulider::from_random_sites(num, bound_min, bound_max)
-        .relaxate_sites(10)
-        .unwrap()
-        .add_edge_sites(None, None)
-        .unwrap()
-        .build()
-        .unwrap();
-
     // count edge sites
     let edge_sites_len = model
         .sites()
@@ -52,8 +280,6 @@ pub fn generate_terrain(
         })
         .count();
 
-    debug!("distributing params...");
-
     let sites = model.sites().to_vec();
 
     // fault
@@ -95,10 +321,24 @@ pub fn generate_terrain(
 
     let land_bias = -(inversed_perlin_noise_curve(config.land_ratio) - 0.5);
 
+    // Makes it more likely for land to appear close to the nearest landmass
+    // center, and less likely further away from it.
+    let get_landmass_falloff = |site: &Site2D| -> f64 {
+        config
+            .landmass_centers
+            .iter()
+            .map(|center| site.distance(center))
+            .min_by(f64::total_cmp)
+            .map_or(0.0, |distance| {
+                (distance / config.landmass_radius).powi(2) - 0.5
+            })
+    };
+
     let base_is_outlet = {
         sites
             .iter()
             .map(|site| {
+                let landmass_falloff = get_landmass_falloff(site);
                 let site = &apply_fault(site);
                 let persistence_scale = 50.;
                 let noise_persistence = octaved_perlin(
@@ -132,7 +372,7 @@ pub fn generate_terrain(
                     1.8,
                 ) * 0.7
                     + 0.5;
-                noise_plate > noise_continent - land_bias
+                noise_plate > noise_continent - land_bias - landmass_falloff
             })
             .collect::<Vec<bool>>()
     };
@@ -146,46 +386,36 @@ pub fn generate_terrain(
         start_index,
         graph,
         config.convex_hull_is_always_outlet,
-    )
-    .unwrap();
-
-    debug!("generating...");
+    )?;
 
     let erodibility_distribution_power = config.erodibility_distribution_power;
-    let parameters = {
-        sites
-            .iter()
-            .enumerate()
-            .map(|(i, site)| {
-                let site = &apply_fault(site);
-                let erodibility_scale = 75.0;
-                let noise_erodibility = (1.0
-                    - octaved_perlin(
-                        &perlin,
-                        site.x / erodibility_scale,
-                        site.y / erodibility_scale,
-                        5,
-                        0.7,
-                        2.2,
-                    ) * 2.0)
-                    .abs()
-                    .powf(erodibility_distribution_power)
-                    * 0.5
-                    + 0.1;
-
-                TopographicalParameters::default()
-                    .set_erodibility(noise_erodibility)
-                    .set_is_outlet(is_outlet[i])
-                    .set_max_slope(Some(config.global_max_slope))
-            })
-            .collect::<Vec<TopographicalParameters>>()
-    };
+    let parameters = sites
+        .iter()
+        .enumerate()
+        .map(|(i, site)| {
+            let site = &apply_fault(site);
+            let erodibility_scale = 75.0;
+            let noise_erodibility = (1.0
+                - octaved_perlin(
+                    &perlin,
+                    site.x / erodibility_scale,
+                    site.y / erodibility_scale,
+                    5,
+                    0.7,
+                    2.2,
+                ) * 2.0)
+                .abs()
+                .powf(erodibility_distribution_power)
+                * 0.5
+                + 0.1;
 
-    TerrainGenerator::default()
-        .set_model(model)
-        .set_parameters(parameters)
-        .generate()
-        .unwrap()
+            TopographicalParameters::default()
+                .set_erodibility(noise_erodibility)
+                .set_is_outlet(is_outlet[i])
+                .set_max_slope(Some(config.global_max_slope))
+        })
+        .collect::<Vec<TopographicalParameters>>();
+    Some(parameters)
 }
 
 fn octaved_perlin(
@@ -6,8 +6,10 @@ pub use fastlem::models::surface::sites::Site2D;
 pub use fastlem::models::surface::terrain::Terrain2D;
 
 pub use self::config::Config;
-pub use self::generate::generate_terrain;
+pub use self::error::TerrainGenError;
+pub use self::generate::{Stage, TerrainGeneration, generate_terrain};
 
 mod config;
+mod error;
 mod generate;
 mod math;
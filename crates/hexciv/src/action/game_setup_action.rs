@@ -5,6 +5,11 @@ use leafwing_input_manager::prelude::*;
 pub enum GameSetupAction {
     HostGame,
     JoinGame,
+    CycleNumPlayers,
+    CycleMapSize,
+    CycleMapScript,
+    CycleMapWrapping,
+    CancelLoading,
 }
 
 impl GameSetupAction {
@@ -12,6 +17,11 @@ impl GameSetupAction {
         let mut input_map = InputMap::default();
         input_map.insert(Self::HostGame, KeyCode::KeyH);
         input_map.insert(Self::JoinGame, KeyCode::KeyJ);
+        input_map.insert(Self::CycleNumPlayers, KeyCode::KeyP);
+        input_map.insert(Self::CycleMapSize, KeyCode::KeyM);
+        input_map.insert(Self::CycleMapScript, KeyCode::KeyC);
+        input_map.insert(Self::CycleMapWrapping, KeyCode::KeyR);
+        input_map.insert(Self::CancelLoading, KeyCode::Escape);
         input_map
     }
 }
@@ -0,0 +1,242 @@
+use std::fmt::Display;
+#[cfg(feature = "mapgen")]
+use std::path::PathBuf;
+use std::{env, io, process};
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use hexciv::game_setup::{MapScript, MapSize, MapWrapping};
+use hexciv::map::{GameMap, generate_headless_map};
+#[cfg(feature = "mapgen")]
+use hexciv::map_image::render_map_image;
+use hexciv::resource::{
+    BonusResource, MapBonusResources, MapStrategicResources, StrategicResource,
+};
+use hexciv::terrain::{BaseTerrain, StartingPositions, TerrainFeatures};
+use itertools::Itertools as _;
+use serde::Serialize;
+use strum::VariantArray;
+
+#[cfg(not(feature = "mapgen"))]
+const USAGE: &str = "Usage: hexciv-mapgen <map seed> [--map-size <map size>] [--map-script <map \
+                     script>] [--map-wrapping <map wrapping>] [--num-players <num players>]";
+#[cfg(feature = "mapgen")]
+const USAGE: &str = "Usage: hexciv-mapgen <map seed> [--map-size <map size>] [--map-script <map \
+                     script>] [--map-wrapping <map wrapping>] [--num-players <num players>] \
+                     [--png <path> [--tile-width <pixels>] [--elevation-shading]]";
+
+/// The default width of each tile in the PNG image, in pixels.
+#[cfg(feature = "mapgen")]
+const DEFAULT_TILE_WIDTH: u32 = 16;
+
+#[derive(Debug)]
+struct Args {
+    map_seed: u64,
+    map_size: MapSize,
+    map_script: MapScript,
+    map_wrapping: MapWrapping,
+    num_players: u8,
+    /// The path to also write an overview image of the map to, as PNG.
+    #[cfg(feature = "mapgen")]
+    png_path: Option<PathBuf>,
+    #[cfg(feature = "mapgen")]
+    tile_width: u32,
+    #[cfg(feature = "mapgen")]
+    elevation_shading: bool,
+}
+
+/// The generated map, as written out in JSON.
+#[derive(Serialize)]
+struct GeneratedMap {
+    map_seed: u64,
+    map_size: MapSize,
+    map_script: MapScript,
+    map_wrapping: MapWrapping,
+    num_players: u8,
+    width: u32,
+    height: u32,
+    starting_positions: Vec<TilePos>,
+    /// The tiles in row-major order, starting from the tile at `(0, 0)`.
+    tiles: Vec<GeneratedTile>,
+}
+
+#[derive(Serialize)]
+struct GeneratedTile {
+    x: u32,
+    y: u32,
+    base_terrain: BaseTerrain,
+    terrain_features: Option<TerrainFeatures>,
+    /// The hex edges with a river, numbered counter-clockwise starting from the
+    /// east edge.
+    river_edges: Vec<usize>,
+    /// The hex edges with cliffs, numbered counter-clockwise starting from the
+    /// east edge.
+    cliff_edges: Vec<usize>,
+    bonus_resource: Option<BonusResource>,
+    strategic_resource: Option<StrategicResource>,
+}
+
+/// Generates a map without rendering it, and writes the tile grid to stdout as
+/// JSON.
+///
+/// With the `mapgen` feature, an overview image of the map can also be rendered
+/// on the CPU and written as PNG.
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            process::exit(2);
+        },
+    };
+
+    let mut app = match generate_headless_map(
+        args.map_seed,
+        args.num_players,
+        args.map_size,
+        args.map_script,
+        args.map_wrapping,
+    ) {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("failed to generate map: {err}");
+            process::exit(1);
+        },
+    };
+
+    let tiles = app
+        .world_mut()
+        .run_system_cached(collect_tiles)
+        .expect("the resources should have been generated");
+    let starting_positions = app.world().resource::<StartingPositions>().0.clone();
+    let game_map = app.world().resource::<GameMap>();
+    let map_size = game_map.size();
+
+    #[cfg(feature = "mapgen")]
+    if let Some(png_path) = &args.png_path {
+        let image = render_map_image(
+            &map_size,
+            args.tile_width,
+            |tile_pos| game_map[tile_pos].base_terrain,
+            |tile_pos| game_map[tile_pos].terrain_features,
+            |tile_pos| game_map[tile_pos].river_hex_edges,
+            args.elevation_shading
+                .then_some(|tile_pos: &TilePos| *game_map[tile_pos].elevation),
+        );
+        if let Err(err) = image.save(png_path) {
+            eprintln!("failed to write {}: {err}", png_path.display());
+            process::exit(1);
+        }
+    }
+
+    let generated_map = GeneratedMap {
+        map_seed: args.map_seed,
+        map_size: args.map_size,
+        map_script: args.map_script,
+        map_wrapping: args.map_wrapping,
+        num_players: args.num_players,
+        width: map_size.x,
+        height: map_size.y,
+        starting_positions,
+        tiles,
+    };
+
+    serde_json::to_writer_pretty(io::stdout().lock(), &generated_map)
+        .expect("writing the generated map should not fail");
+    println!();
+}
+
+fn parse_args<I>(mut args: I) -> Result<Args, String>
+where
+    I: Iterator<Item = String>,
+{
+    let map_seed = args
+        .next()
+        .ok_or_else(|| "missing map seed".to_owned())?
+        .parse()
+        .map_err(|err| format!("invalid map seed: {err}"))?;
+    let mut parsed_args = Args {
+        map_seed,
+        map_size: MapSize::default(),
+        map_script: MapScript::default(),
+        map_wrapping: MapWrapping::default(),
+        num_players: 2,
+        #[cfg(feature = "mapgen")]
+        png_path: None,
+        #[cfg(feature = "mapgen")]
+        tile_width: DEFAULT_TILE_WIDTH,
+        #[cfg(feature = "mapgen")]
+        elevation_shading: false,
+    };
+
+    while let Some(flag) = args.next() {
+        #[cfg(feature = "mapgen")]
+        if flag == "--elevation-shading" {
+            parsed_args.elevation_shading = true;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--map-size" => parsed_args.map_size = parse_variant(&value)?,
+            "--map-script" => parsed_args.map_script = parse_variant(&value)?,
+            "--map-wrapping" => parsed_args.map_wrapping = parse_variant(&value)?,
+            "--num-players" => {
+                parsed_args.num_players = value
+                    .parse()
+                    .map_err(|err| format!("invalid number of players: {err}"))?;
+            },
+            #[cfg(feature = "mapgen")]
+            "--png" => parsed_args.png_path = Some(value.into()),
+            #[cfg(feature = "mapgen")]
+            "--tile-width" => {
+                parsed_args.tile_width = value
+                    .parse()
+                    .ok()
+                    .filter(|&tile_width| tile_width > 0)
+                    .ok_or_else(|| format!("invalid tile width: {value}"))?;
+            },
+            _ => return Err(format!("unexpected argument: {flag}")),
+        }
+    }
+
+    Ok(parsed_args)
+}
+
+/// Parses the variant of the enum from its name, ignoring case.
+fn parse_variant<T>(value: &str) -> Result<T, String>
+where
+    T: Copy + Display + VariantArray,
+{
+    T::VARIANTS
+        .iter()
+        .copied()
+        .find(|variant| variant.to_string().eq_ignore_ascii_case(value))
+        .ok_or_else(|| {
+            format!(
+                "invalid value: {value} (expected one of: {})",
+                T::VARIANTS.iter().join(", ")
+            )
+        })
+}
+
+fn collect_tiles(
+    game_map: Res<GameMap>,
+    map_bonus_resources: Res<MapBonusResources>,
+    map_strategic_resources: Res<MapStrategicResources>,
+) -> Vec<GeneratedTile> {
+    game_map
+        .iter()
+        .map(|(tile_pos, tile)| GeneratedTile {
+            x: tile_pos.x,
+            y: tile_pos.y,
+            base_terrain: tile.base_terrain,
+            terrain_features: tile.terrain_features,
+            river_edges: tile.river_hex_edges.iter_ones().collect(),
+            cliff_edges: tile.cliff_hex_edges.iter_ones().collect(),
+            bonus_resource: map_bonus_resources.get(&tile_pos),
+            strategic_resource: map_strategic_resources.get(&tile_pos),
+        })
+        .collect()
+}
@@ -2,31 +2,48 @@ use bevy::log::LogPlugin;
 use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::*;
 use bevy_matchbox::MatchboxSocket;
-use bevy_pancam::PanCamPlugin;
+use bevy_pancam::{PanCamPlugin, PanCamSystemSet};
 use hexciv::action::{CursorAction, GameSetupAction, GlobalAction, UnitAction};
 use hexciv::asset::FontHandle;
 use hexciv::dev_tools::TileLabelPlugin;
-use hexciv::game_setup::{GameSetupSet, HostingSet, InGameSet, JoiningSet, host_game, join_game};
+use hexciv::fog_of_war::{
+    spawn_fog_of_war_tiles, update_fog_of_war_tiles, update_player_visibility,
+    update_unit_tile_visibility,
+};
+use hexciv::game_setup::{
+    GameSetupOptions, GameSetupSet, HostingSet, InGameSet, JoiningSet, LoadingSet, MapWrapping,
+    cancel_loading, cycle_map_script, cycle_map_size, cycle_map_wrapping, cycle_num_players,
+    handle_host_disconnected, host_game, join_game, update_game_setup_actions_legend,
+};
 use hexciv::input::{CursorPos, CursorTilePos, update_cursor_pos, update_cursor_tile_pos};
 use hexciv::input_dialog::InputDialogPlugin;
+use hexciv::loading_screen::{hide_loading_screen, show_loading_screen, update_loading_screen};
 use hexciv::peer::{
     HostBroadcast, HostId, OurPeerId, PeerConnected, ReceiveHostBroadcastSet, ReceiveRequestSet,
-    Request, SocketRxQueue, dispatch_host_broadcast, dispatch_request, handle_peer_connected,
-    receive_host_broadcast, receive_request, send_host_broadcast, send_request, wait_for_peers,
+    Request, SocketRxQueue, announce_connected_peers, dispatch_host_broadcast, dispatch_request,
+    handle_peer_connected, receive_host_broadcast, receive_request, send_host_broadcast,
+    send_request, wait_for_peers,
 };
 use hexciv::player::{OurPlayer, spawn_players};
+use hexciv::resource::{
+    generate_bonus_resources, spawn_resource_tiles, update_strategic_resource_visibility,
+};
 use hexciv::state::{GameState, InputDialogState, MultiplayerState, TurnState};
-use hexciv::terrain::{SpawnTilemapSet, post_spawn_tilemap, spawn_tilemap, upgrade_camera};
+use hexciv::terrain::{
+    ElevationClassifierConfig, MapTerrainGeneration, SpawnTilemapSet, advance_terrain_generation,
+    choose_starting_positions, post_spawn_tilemap, spawn_terrain_tiles, spawn_tilemap,
+    start_terrain_generation, upgrade_camera, wrap_camera_around,
+};
 use hexciv::turn::{
     CurrentTurn, TurnInProgressSet, TurnStarted, enable_global_actions, enable_unit_actions,
     handle_turn_started, mark_turn_in_progress,
 };
 use hexciv::unit::{
     ActionsLegend, UnitEntityMap, UnitMoved, UnitSelected, UnitSpawned, cycle_ready_unit,
-    focus_camera_on_active_unit, handle_unit_moved, handle_unit_selected, handle_unit_spawned,
-    has_ready_units, mark_active_unit_fortified, mark_active_unit_out_of_orders,
-    move_active_unit_to, reset_movement_points, select_unit, should_move_active_unit_to,
-    spawn_starting_units,
+    execute_move_orders, focus_camera_on_active_unit, handle_unit_moved, handle_unit_selected,
+    handle_unit_spawned, has_ready_units, mark_active_unit_fortified,
+    mark_active_unit_out_of_orders, move_active_unit_to, reset_movement_points, select_unit,
+    should_move_active_unit_to, spawn_starting_units, update_movement_path_preview,
 };
 use leafwing_input_manager::common_conditions::action_just_pressed;
 use leafwing_input_manager::prelude::*;
@@ -94,6 +111,8 @@ fn main() {
     .init_resource::<SocketRxQueue>()
     .init_resource::<CursorPos>()
     .init_resource::<UnitEntityMap>()
+    .init_resource::<GameSetupOptions>()
+    .init_resource::<ElevationClassifierConfig>()
     .init_state::<MultiplayerState>()
     .init_state::<InputDialogState>()
     .init_state::<GameState>()
@@ -111,14 +130,28 @@ fn main() {
             HostingSet.run_if(in_state(MultiplayerState::Hosting)),
             JoiningSet.run_if(in_state(MultiplayerState::Joining)),
             GameSetupSet.run_if(in_state(GameState::GameSetup)),
+            LoadingSet.run_if(in_state(GameState::Loading)),
             InGameSet.run_if(in_state(GameState::InGame)),
             TurnInProgressSet.run_if(in_state(TurnState::InProgress)),
         ),
     )
     .add_systems(Startup, setup)
+    .add_systems(
+        OnEnter(GameState::Loading),
+        (show_loading_screen, start_terrain_generation),
+    )
+    .add_systems(OnExit(GameState::Loading), hide_loading_screen)
     .add_systems(
         OnEnter(GameState::InGame),
-        (spawn_tilemap, post_spawn_tilemap)
+        (
+            spawn_tilemap,
+            post_spawn_tilemap,
+            spawn_terrain_tiles,
+            spawn_fog_of_war_tiles,
+            choose_starting_positions,
+            generate_bonus_resources,
+            spawn_resource_tiles,
+        )
             .chain()
             .in_set(SpawnTilemapSet),
     )
@@ -133,6 +166,7 @@ fn main() {
                 .chain()
                 .run_if(in_state(MultiplayerState::Joining))
                 .in_set(ReceiveHostBroadcastSet),
+            announce_connected_peers.run_if(in_state(MultiplayerState::Hosting)),
             handle_peer_connected,
         )
             .chain(),
@@ -151,6 +185,7 @@ fn main() {
         OnEnter(TurnState::InProgress),
         (
             reset_movement_points,
+            execute_move_orders,
             cycle_ready_unit,
             handle_unit_selected,
             focus_camera_on_active_unit,
@@ -162,9 +197,15 @@ fn main() {
         Update,
         (
             (
+                cycle_num_players.run_if(action_just_pressed(GameSetupAction::CycleNumPlayers)),
+                cycle_map_size.run_if(action_just_pressed(GameSetupAction::CycleMapSize)),
+                cycle_map_script.run_if(action_just_pressed(GameSetupAction::CycleMapScript)),
+                cycle_map_wrapping.run_if(action_just_pressed(GameSetupAction::CycleMapWrapping)),
+                update_game_setup_actions_legend.run_if(resource_changed::<GameSetupOptions>),
                 host_game.run_if(action_just_pressed(GameSetupAction::HostGame)),
                 join_game.run_if(action_just_pressed(GameSetupAction::JoinGame)),
             )
+                .chain()
                 .run_if(in_state(MultiplayerState::Inactive)),
             wait_for_peers
                 .before(send_host_broadcast)
@@ -173,6 +214,23 @@ fn main() {
         )
             .in_set(GameSetupSet),
     )
+    .add_systems(
+        Update,
+        (
+            advance_terrain_generation,
+            update_loading_screen.run_if(resource_exists::<MapTerrainGeneration>),
+            cancel_loading.run_if(action_just_pressed(GameSetupAction::CancelLoading)),
+        )
+            .chain()
+            .in_set(LoadingSet),
+    )
+    .add_systems(
+        Update,
+        handle_host_disconnected
+            .run_if(resource_exists::<MatchboxSocket>.and(resource_exists::<HostId>))
+            .in_set(JoiningSet)
+            .in_set(LoadingSet),
+    )
     .add_systems(
         Update,
         (
@@ -197,7 +255,8 @@ fn main() {
             )
                 .in_set(JoiningSet),
         )
-            .run_if(resource_exists::<OurPeerId>.and(resource_exists::<HostId>)),
+            .run_if(resource_exists::<OurPeerId>.and(resource_exists::<HostId>))
+            .in_set(InGameSet),
     )
     .add_systems(
         Update,
@@ -242,6 +301,33 @@ fn main() {
         )
             .in_set(TurnInProgressSet),
     )
+    .add_systems(
+        Update,
+        (
+            update_player_visibility
+                .after(handle_unit_spawned)
+                .after(handle_unit_moved)
+                .run_if(on_event::<UnitSpawned>.or(on_event::<UnitMoved>)),
+            (update_fog_of_war_tiles, update_unit_tile_visibility)
+                .run_if(resource_exists::<OurPlayer>),
+        )
+            .chain()
+            .in_set(InGameSet),
+    )
+    .add_systems(
+        Update,
+        update_strategic_resource_visibility
+            .run_if(resource_exists::<OurPlayer>)
+            .in_set(InGameSet),
+    )
+    .add_systems(
+        Update,
+        wrap_camera_around
+            .after(PanCamSystemSet)
+            .before(update_cursor_pos)
+            .run_if(resource_equals(MapWrapping::WrapEastWest))
+            .in_set(InGameSet),
+    )
     .add_systems(
         Update,
         (update_cursor_pos, update_cursor_tile_pos)
@@ -270,6 +356,15 @@ fn main() {
         handle_unit_selected
             .run_if(on_event::<UnitSelected>)
             .in_set(InGameSet),
+    )
+    .add_systems(
+        Update,
+        update_movement_path_preview
+            .after(update_cursor_tile_pos)
+            .after(handle_unit_selected)
+            .after(handle_unit_moved)
+            .run_if(resource_exists::<OurPlayer>)
+            .in_set(InGameSet),
     );
 
     #[cfg(debug_assertions)]
@@ -281,10 +376,11 @@ fn main() {
 }
 
 fn setup(mut commands: Commands, font_handle: Res<FontHandle>) {
-    commands.spawn(Camera2d);
+    // The main camera renders the UI, rather than any of the wrap-around cameras.
+    commands.spawn((Camera2d, IsDefaultUiCamera));
     commands
         .spawn((
-            Text::new("[H] Host game\n[J] Join game"),
+            Text::default(),
             TextFont {
                 font: font_handle.0.clone(),
                 font_size: 24.0,
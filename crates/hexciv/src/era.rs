@@ -0,0 +1,22 @@
+use bevy::prelude::*;
+
+/// See <https://civilization.fandom.com/wiki/Era_(Civ6)>
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum Era {
+    #[default]
+    Ancient,
+    Classical,
+    Medieval,
+    Renaissance,
+    Industrial,
+    Modern,
+    Atomic,
+    Information,
+}
+
+/// The era a player has reached.
+///
+/// Every player stays in the [`Era::Ancient`] era for now, as there is nothing
+/// yet which advances the era (e.g. the tech tree).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Component)]
+pub struct PlayerEra(pub Era);
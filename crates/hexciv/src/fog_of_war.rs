@@ -0,0 +1,199 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::game_setup::MapWrapping;
+use crate::layer::{FogOfWarLayer, FogOfWarLayerFilter, UnitLayersFilter, UnitStateLayerFilter};
+use crate::map::{GameMap, Tile};
+use crate::player::{OurPlayer, Player};
+use crate::terrain::neighboring_positions;
+use crate::unit::{Unit, UnitFilter};
+
+/// The distance in tiles that units can see.
+pub const UNIT_SIGHT_RANGE: u32 = 2;
+
+/// The color of the fog over the tiles which have never been seen.
+const UNEXPLORED_FOG_COLOR: &str = "#E9D4B1";
+/// The color of the fog over the tiles which have been seen before, but are not
+/// currently visible.
+const EXPLORED_FOG_COLOR: &str = "#5C3F2180";
+
+/// What the player knows of the map.
+#[derive(Clone, Debug, Default, Component)]
+pub struct PlayerVisibility {
+    /// The tiles which have ever been inside any unit's sight range, as they
+    /// were last seen.
+    ///
+    /// This is the last known map of the player, which might no longer match
+    /// the actual map outside of the visible tiles.
+    remembered_tiles: HashMap<TilePos, Tile>,
+    /// The tiles which are currently inside any unit's sight range.
+    visible: HashSet<TilePos>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum TileVisibility {
+    /// The tile has never been seen.
+    Unexplored,
+    /// The tile has been seen before, but is not currently visible.
+    Explored,
+    /// The tile is currently visible.
+    Visible,
+}
+
+impl PlayerVisibility {
+    pub fn get(&self, tile_pos: &TilePos) -> TileVisibility {
+        if self.visible.contains(tile_pos) {
+            TileVisibility::Visible
+        } else if self.remembered_tiles.contains_key(tile_pos) {
+            TileVisibility::Explored
+        } else {
+            TileVisibility::Unexplored
+        }
+    }
+
+    pub fn is_explored(&self, tile_pos: &TilePos) -> bool {
+        self.remembered_tiles.contains_key(tile_pos)
+    }
+
+    pub fn is_visible(&self, tile_pos: &TilePos) -> bool {
+        self.visible.contains(tile_pos)
+    }
+
+    /// Returns the tile as it was last seen by the player, or `None` if the
+    /// player has never seen it.
+    pub fn remembered_tile(&self, tile_pos: &TilePos) -> Option<&Tile> {
+        self.remembered_tiles.get(tile_pos)
+    }
+
+    /// Replaces the visible tiles, and remembers them as they are on the map.
+    /// The tiles which are no longer visible remain explored.
+    fn set_visible(&mut self, visible: HashSet<TilePos>, game_map: &GameMap) {
+        self.remembered_tiles.extend(
+            visible
+                .iter()
+                .map(|tile_pos| (*tile_pos, game_map[tile_pos])),
+        );
+        self.visible = visible;
+    }
+}
+
+/// Updates the tiles which each player can see, from the sight range of their
+/// units.
+pub fn update_player_visibility(
+    game_map: Res<GameMap>,
+    map_wrapping: Res<MapWrapping>,
+    mut player_query: Query<(&Player, &mut PlayerVisibility), With<Player>>,
+    unit_query: Query<(&Unit, &TilePos), UnitFilter>,
+) {
+    let map_size = &game_map.size();
+
+    for (player, mut player_visibility) in player_query.iter_mut() {
+        let visible: HashSet<_> = unit_query
+            .iter()
+            .filter(|(unit, _tile_pos)| unit.civ == player.civ)
+            .flat_map(|(_unit, tile_pos)| {
+                positions_in_sight_range(tile_pos, UNIT_SIGHT_RANGE, map_size, *map_wrapping)
+            })
+            .collect();
+        if visible != player_visibility.visible {
+            player_visibility.set_visible(visible, &game_map);
+        }
+    }
+}
+
+pub fn spawn_fog_of_war_tiles(
+    mut commands: Commands,
+    game_map: Res<GameMap>,
+    fog_of_war_tilemap_query: Single<(Entity, &mut TileStorage), FogOfWarLayerFilter>,
+) {
+    let (fog_of_war_tilemap_entity, mut fog_of_war_tile_storage) =
+        fog_of_war_tilemap_query.into_inner();
+
+    for (tile_pos, _tile) in game_map.iter() {
+        let tile_entity = commands
+            .spawn(TileBundle {
+                position: tile_pos,
+                tilemap_id: TilemapId(fog_of_war_tilemap_entity),
+                color: TileColor(Srgba::hex(UNEXPLORED_FOG_COLOR).unwrap().into()),
+                ..Default::default()
+            })
+            .insert(FogOfWarLayer)
+            .id();
+        fog_of_war_tile_storage.set(&tile_pos, tile_entity);
+    }
+}
+
+/// Hides the tiles which our player has never seen, and dims the tiles which
+/// are not currently visible to our player.
+pub fn update_fog_of_war_tiles(
+    our_player: Res<OurPlayer>,
+    player_query: Query<(Ref<PlayerVisibility>,), With<Player>>,
+    mut fog_of_war_tile_query: Query<
+        (Ref<TilePos>, &mut TileColor, &mut TileVisible),
+        FogOfWarLayerFilter,
+    >,
+) {
+    let Ok((player_visibility,)) = player_query.get(our_player.0) else {
+        return;
+    };
+
+    for (tile_pos, mut tile_color, mut tile_visible) in fog_of_war_tile_query.iter_mut() {
+        if !player_visibility.is_changed() && !tile_pos.is_added() {
+            continue;
+        }
+        let fog_color = match player_visibility.get(&tile_pos) {
+            TileVisibility::Unexplored => UNEXPLORED_FOG_COLOR,
+            TileVisibility::Explored => EXPLORED_FOG_COLOR,
+            TileVisibility::Visible => {
+                tile_visible.set_if_neq(TileVisible(false));
+                continue;
+            },
+        };
+        *tile_color = TileColor(Srgba::hex(fog_color).unwrap().into());
+        tile_visible.set_if_neq(TileVisible(true));
+    }
+}
+
+/// Hides the units on the tiles which are not currently visible to our player.
+pub fn update_unit_tile_visibility(
+    our_player: Res<OurPlayer>,
+    player_query: Query<(&PlayerVisibility,), With<Player>>,
+    mut unit_tile_query: Query<(&TilePos, &mut TileVisible), UnitLayersFilter>,
+    mut unit_state_tile_query: Query<(&TilePos, &mut TileVisible), UnitStateLayerFilter>,
+) {
+    let Ok((player_visibility,)) = player_query.get(our_player.0) else {
+        return;
+    };
+
+    for (tile_pos, mut tile_visible) in unit_tile_query
+        .iter_mut()
+        .chain(unit_state_tile_query.iter_mut())
+    {
+        tile_visible.set_if_neq(TileVisible(player_visibility.is_visible(tile_pos)));
+    }
+}
+
+/// Returns the positions of the tiles within the sight range of the tile,
+/// including the tile itself.
+pub fn positions_in_sight_range(
+    tile_pos: &TilePos,
+    sight_range: u32,
+    map_size: &TilemapSize,
+    map_wrapping: MapWrapping,
+) -> HashSet<TilePos> {
+    let mut positions = HashSet::from([*tile_pos]);
+    let mut frontier = vec![*tile_pos];
+    for _ in 0..sight_range {
+        frontier = frontier
+            .iter()
+            .flat_map(|tile_pos| {
+                let neighbor_positions = neighboring_positions(tile_pos, map_size, map_wrapping);
+                neighbor_positions.iter().copied().collect::<Vec<_>>()
+            })
+            .filter(|neighbor_pos| positions.insert(*neighbor_pos))
+            .collect();
+    }
+    positions
+}
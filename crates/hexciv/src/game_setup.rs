@@ -1,16 +1,26 @@
 use std::sync::LazyLock;
 
 use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use bevy_matchbox::MatchboxSocket;
+use bevy_matchbox::prelude::PeerState;
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
+use strum::VariantArray;
 
 use crate::input_dialog::{InputDialogCallback, InputDialogValue};
-use crate::peer::start_matchbox_socket;
-use crate::state::{InputDialogState, MultiplayerState};
+use crate::peer::{HostId, OurPeerId, SocketRxQueue, start_matchbox_socket};
+use crate::state::{GameState, InputDialogState, MultiplayerState};
+use crate::terrain::{ElevationClassifierConfig, MapTerrain, MapTerrainGeneration};
 use crate::unit::ActionsLegend;
 
 const GAME_SESSION_ID_WORD_LEN: usize = 2;
 
+const MIN_NUM_PLAYERS: u8 = 2;
+const MAX_NUM_PLAYERS: u8 = 4;
+
+pub const GAME_SETUP_ACTIONS_LEGEND: &str = "[H] Host game\n[J] Join game";
+
 static BIP39_ENGLISH_WORDLIST: LazyLock<Vec<String>> = LazyLock::new(|| {
     let wordlist = include_str!(concat!(
         env!("BEVY_ASSET_ROOT"),
@@ -25,6 +35,10 @@ pub struct GameSetup {
     pub map_seed: u64,
     pub game_seed: u64,
     pub num_players: u8,
+    pub map_size: MapSize,
+    pub map_script: MapScript,
+    pub map_wrapping: MapWrapping,
+    pub elevation_classifier_config: ElevationClassifierConfig,
 }
 
 #[derive(Debug, Display, Resource)]
@@ -40,9 +54,149 @@ pub struct GameRng(pub fastrand::Rng);
 #[derive(Debug, Resource)]
 pub struct NumPlayers(pub u8);
 
+/// The game setup chosen by the host before hosting a game.
+#[derive(Debug, Resource)]
+pub struct GameSetupOptions {
+    pub num_players: u8,
+    pub map_size: MapSize,
+    pub map_script: MapScript,
+    pub map_wrapping: MapWrapping,
+}
+
+/// See <https://civilization.fandom.com/wiki/Map_(Civ6)#Map_sizes>
+#[derive(
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+    Default,
+    Display,
+    Deserialize,
+    Serialize,
+    VariantArray,
+    Resource,
+)]
+pub enum MapSize {
+    Duel,
+    Tiny,
+    #[default]
+    Small,
+    Standard,
+    Large,
+    Huge,
+}
+
+/// See <https://civilization.fandom.com/wiki/Map_(Civ6)#Map_types>
+#[derive(
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+    Default,
+    Display,
+    Deserialize,
+    Serialize,
+    VariantArray,
+    Resource,
+)]
+pub enum MapScript {
+    /// Two or more large landmasses separated by ocean.
+    #[default]
+    Continents,
+    /// One large landmass.
+    Pangaea,
+    /// Many small landmasses.
+    Islands,
+    /// Landmasses shaped by the noise alone.
+    Fractal,
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+    Default,
+    Display,
+    Deserialize,
+    Serialize,
+    VariantArray,
+    Resource,
+)]
+pub enum MapWrapping {
+    /// The map has hard edges on all sides.
+    #[default]
+    NoWrap,
+    /// The map wraps around from the east edge to the west edge, like a
+    /// cylinder.
+    WrapEastWest,
+}
+
+impl MapWrapping {
+    pub fn wraps_east_west(&self) -> bool {
+        matches!(self, Self::WrapEastWest)
+    }
+}
+
+// IMPORTANT: The map's dimensions must both be even numbers, due to the
+// assumptions being made in our calculations.
+const _: () = {
+    let mut i = 0;
+    while i < MapSize::VARIANTS.len() {
+        let TilemapSize { x, y } = MapSize::VARIANTS[i].tilemap_size();
+        assert!(
+            x % 2 == 0 && y % 2 == 0,
+            "map dimensions should both be even numbers"
+        );
+        i += 1;
+    }
+};
+
+impl Default for GameSetupOptions {
+    fn default() -> Self {
+        Self {
+            num_players: MIN_NUM_PLAYERS,
+            map_size: MapSize::default(),
+            map_script: MapScript::default(),
+            map_wrapping: MapWrapping::default(),
+        }
+    }
+}
+
+impl MapSize {
+    /// Returns the maximum number of players on the map.
+    pub const fn max_num_players(&self) -> u8 {
+        match self {
+            Self::Duel => 2,
+            Self::Tiny | Self::Small | Self::Standard | Self::Large | Self::Huge => MAX_NUM_PLAYERS,
+        }
+    }
+
+    /// Returns the number of tiles along each side of the map.
+    pub const fn tilemap_size(&self) -> TilemapSize {
+        match self {
+            Self::Duel => TilemapSize { x: 44, y: 26 },
+            Self::Tiny => TilemapSize { x: 60, y: 38 },
+            Self::Small => TilemapSize { x: 74, y: 46 },
+            Self::Standard => TilemapSize { x: 84, y: 54 },
+            Self::Large => TilemapSize { x: 96, y: 60 },
+            Self::Huge => TilemapSize { x: 106, y: 66 },
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, SystemSet)]
 pub struct GameSetupSet;
 
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, SystemSet)]
+pub struct LoadingSet;
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, SystemSet)]
 pub struct InGameSet;
 
@@ -52,9 +206,60 @@ pub struct HostingSet;
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, SystemSet)]
 pub struct JoiningSet;
 
+/// Returns the actions legend of the game setup, along with the options chosen
+/// for hosting a game.
+pub fn game_setup_actions_legend(game_setup_options: &GameSetupOptions) -> String {
+    let GameSetupOptions {
+        num_players,
+        map_size,
+        map_script,
+        map_wrapping,
+    } = game_setup_options;
+    format!(
+        "{GAME_SETUP_ACTIONS_LEGEND}\n\n[P] Players: {num_players}\n[M] Map size: {map_size}\n\
+         [C] Map script: {map_script}\n[R] Map wrapping: {map_wrapping}"
+    )
+}
+
+pub fn update_game_setup_actions_legend(
+    game_setup_options: Res<GameSetupOptions>,
+    actions_legend_text_query: Single<(&mut Text,), With<ActionsLegend>>,
+) {
+    let (mut actions_legend_text,) = actions_legend_text_query.into_inner();
+
+    actions_legend_text.0 = game_setup_actions_legend(&game_setup_options);
+}
+
+/// Cycles through the number of players which fit on the chosen map size.
+pub fn cycle_num_players(mut game_setup_options: ResMut<GameSetupOptions>) {
+    let max_num_players = game_setup_options.map_size.max_num_players();
+    game_setup_options.num_players = if game_setup_options.num_players < max_num_players {
+        game_setup_options.num_players + 1
+    } else {
+        MIN_NUM_PLAYERS
+    };
+}
+
+pub fn cycle_map_size(mut game_setup_options: ResMut<GameSetupOptions>) {
+    let map_size = next_variant(game_setup_options.map_size);
+    game_setup_options.map_size = map_size;
+    game_setup_options.num_players = game_setup_options
+        .num_players
+        .min(map_size.max_num_players());
+}
+
+pub fn cycle_map_script(mut game_setup_options: ResMut<GameSetupOptions>) {
+    game_setup_options.map_script = next_variant(game_setup_options.map_script);
+}
+
+pub fn cycle_map_wrapping(mut game_setup_options: ResMut<GameSetupOptions>) {
+    game_setup_options.map_wrapping = next_variant(game_setup_options.map_wrapping);
+}
+
 pub fn host_game(
     mut commands: Commands,
     mut next_multiplayer_state: ResMut<NextState<MultiplayerState>>,
+    game_setup_options: Res<GameSetupOptions>,
     actions_legend_text_query: Single<(&mut Text,), With<ActionsLegend>>,
 ) {
     let (mut actions_legend_text,) = actions_legend_text_query.into_inner();
@@ -67,15 +272,24 @@ pub fn host_game(
             .try_into()
             .unwrap(),
     );
-    let num_players = NumPlayers(2);
+    let num_players = NumPlayers(game_setup_options.num_players);
+    let map_size = game_setup_options.map_size;
+    let map_script = game_setup_options.map_script;
+    let map_wrapping = game_setup_options.map_wrapping;
 
     actions_legend_text.0 = format!(
-        "Hosting game...\nGame session ID: {game_session_id}\nPlayers: {num_players}\n",
+        "Hosting game...\nGame session ID: {game_session_id}-{num_players}\n\
+         Players: {num_players}\n\
+         Map size: {map_size}\nMap script: {map_script}\n\
+         Map wrapping: {map_wrapping}\n",
         num_players = num_players.0
     );
 
     commands.insert_resource(game_session_id);
     commands.insert_resource(num_players);
+    commands.insert_resource(map_size);
+    commands.insert_resource(map_script);
+    commands.insert_resource(map_wrapping);
     commands.insert_resource(MapRng(fastrand::Rng::new()));
     commands.insert_resource(GameRng(fastrand::Rng::new()));
 
@@ -105,7 +319,41 @@ fn join_game_callback(
     mut commands: Commands,
     input_dialog_value: Res<InputDialogValue>,
     mut next_input_dialog_state: ResMut<NextState<InputDialogState>>,
+    actions_legend_text_query: Single<(&mut Text,), With<ActionsLegend>>,
 ) {
+    let (mut actions_legend_text,) = actions_legend_text_query.into_inner();
+
+    // The game session ID may be followed by the number of players, which the
+    // matchbox server needs to match the peers.
+    let segments: Vec<_> = input_dialog_value
+        .0
+        .split(&['-', ' '])
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let num_players_segment = segments
+        .last()
+        .filter(|&&last_segment| {
+            segments.len() > GAME_SESSION_ID_WORD_LEN
+                && !BIP39_ENGLISH_WORDLIST.iter().any(|w| w == last_segment)
+        })
+        .copied();
+    let num_players = if let Some(num_players_segment) = num_players_segment {
+        let Some(num_players) = num_players_segment
+            .parse::<u8>()
+            .ok()
+            .filter(|num_players| (MIN_NUM_PLAYERS..=MAX_NUM_PLAYERS).contains(num_players))
+        else {
+            // Invalid number of players - let the user try to input again.
+            actions_legend_text.0 = format!(
+                "Joining game...\nInvalid number of players: {num_players_segment} (expected \
+                 {MIN_NUM_PLAYERS} to {MAX_NUM_PLAYERS})\n"
+            );
+            return;
+        };
+        num_players
+    } else {
+        MIN_NUM_PLAYERS
+    };
     let words: Result<[String; 2], _> = input_dialog_value
         .0
         .split(&['-', ' '])
@@ -120,14 +368,106 @@ fn join_game_callback(
         .try_into();
     let Ok(words) = words else {
         // Invalid game session ID - let the user try to input again.
-        // TODO: Some kind of error indication?
+        actions_legend_text.0 = "Joining game...\nInvalid game session ID\n".to_owned();
         return;
     };
+    actions_legend_text.0 = "Joining game...\n".to_owned();
     commands.insert_resource(GameSessionId(words));
-    commands.insert_resource(NumPlayers(2));
+    commands.insert_resource(NumPlayers(num_players));
 
     commands.run_system_cached(start_matchbox_socket);
 
     commands.remove_resource::<InputDialogCallback>();
     next_input_dialog_state.set(InputDialogState::Hidden);
 }
+
+/// Leaves the game session while the map is being generated, and goes back to
+/// the game setup.
+pub fn cancel_loading(
+    mut commands: Commands,
+    mut socket_rx_queue: ResMut<SocketRxQueue>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_multiplayer_state: ResMut<NextState<MultiplayerState>>,
+    game_setup_options: Res<GameSetupOptions>,
+    actions_legend_text_query: Single<(&mut Text,), With<ActionsLegend>>,
+) {
+    let (mut actions_legend_text,) = actions_legend_text_query.into_inner();
+
+    info!("cancelled loading, leaving game session");
+
+    // If we are the host, the joiners notice that we have disconnected, and
+    // leave the game session too.
+    leave_loading(&mut commands, &mut socket_rx_queue);
+
+    actions_legend_text.0 = game_setup_actions_legend(&game_setup_options);
+
+    next_multiplayer_state.set(MultiplayerState::Inactive);
+    next_game_state.set(GameState::GameSetup);
+}
+
+/// Leaves the game session once the host has disconnected while the map is
+/// being generated, and goes back to the game setup.
+///
+/// This should not be called on the host.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_host_disconnected(
+    mut commands: Commands,
+    mut socket: ResMut<MatchboxSocket>,
+    mut socket_rx_queue: ResMut<SocketRxQueue>,
+    host_id: Res<HostId>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_multiplayer_state: ResMut<NextState<MultiplayerState>>,
+    game_setup_options: Res<GameSetupOptions>,
+    actions_legend_text_query: Single<(&mut Text,), With<ActionsLegend>>,
+) {
+    let (mut actions_legend_text,) = actions_legend_text_query.into_inner();
+
+    let host_disconnected = socket
+        .update_peers()
+        .into_iter()
+        .any(|(peer_id, peer_state)| peer_id == host_id.0 && peer_state == PeerState::Disconnected);
+    if !host_disconnected {
+        return;
+    }
+
+    warn!(host_id = ?host_id.0, "host disconnected, leaving game session");
+
+    leave_loading(&mut commands, &mut socket_rx_queue);
+
+    actions_legend_text.0 = format!(
+        "The host has left the game session.\n\n{}",
+        game_setup_actions_legend(&game_setup_options)
+    );
+
+    next_multiplayer_state.set(MultiplayerState::Inactive);
+    next_game_state.set(GameState::GameSetup);
+}
+
+/// Removes the resources of the game session while the map is being generated.
+fn leave_loading(commands: &mut Commands, socket_rx_queue: &mut SocketRxQueue) {
+    // Dropping the terrain generation cancels its remaining stages.
+    commands.remove_resource::<MapTerrainGeneration>();
+    commands.remove_resource::<MapTerrain>();
+    // Dropping the socket disconnects from the peers.
+    commands.remove_resource::<MatchboxSocket>();
+    commands.remove_resource::<OurPeerId>();
+    commands.remove_resource::<HostId>();
+    commands.remove_resource::<GameSessionId>();
+    commands.remove_resource::<NumPlayers>();
+    commands.remove_resource::<MapSize>();
+    commands.remove_resource::<MapScript>();
+    commands.remove_resource::<MapWrapping>();
+    commands.remove_resource::<MapRng>();
+    commands.remove_resource::<GameRng>();
+    socket_rx_queue.0.clear();
+}
+
+/// Returns the variant after the given one, going back to the first variant
+/// after the last one.
+fn next_variant<T: VariantArray + PartialEq + Copy>(value: T) -> T {
+    let i = T::VARIANTS
+        .iter()
+        .position(|variant| *variant == value)
+        .expect("value should be one of the variants");
+    T::VARIANTS[(i + 1) % T::VARIANTS.len()]
+}
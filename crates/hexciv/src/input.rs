@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::*;
 
+use crate::game_setup::MapWrapping;
 use crate::layer::BaseTerrainLayerFilter;
+use crate::terrain::WrapAroundCamera;
 
 #[derive(Debug, Resource)]
 pub struct CursorPos(pub Vec2);
@@ -19,7 +21,7 @@ impl Default for CursorPos {
 
 /// Keeps the cursor position updated based on any [`CursorMoved`] events.
 pub fn update_cursor_pos(
-    camera_query: Query<(&GlobalTransform, &Camera)>,
+    camera_query: Query<(&GlobalTransform, &Camera), Without<WrapAroundCamera>>,
     mut cursor_moved_events: EventReader<CursorMoved>,
     mut cursor_pos: ResMut<CursorPos>,
 ) {
@@ -39,6 +41,7 @@ pub fn update_cursor_pos(
 pub fn update_cursor_tile_pos(
     mut commands: Commands,
     cursor_pos: Res<CursorPos>,
+    map_wrapping: Res<MapWrapping>,
     tilemap_query: Single<
         (
             &Transform,
@@ -63,16 +66,35 @@ pub fn update_cursor_tile_pos(
         let cursor_in_map_pos = map_transform.compute_matrix().inverse() * cursor_pos;
         cursor_in_map_pos.xy()
     };
+    // Where the map wraps around east-west, the cursor may be hovering over the
+    // map as rendered by one of the wrap-around cameras, so it has to be brought
+    // back over the map.
+    let map_width = map_size.x as f32 * grid_size.x;
+    let cursor_in_map_positions = if map_wrapping.wraps_east_west() {
+        let x = (cursor_in_map_pos.x + map_width / 2.0).rem_euclid(map_width) - map_width / 2.0;
+        vec![
+            cursor_in_map_pos.with_x(x),
+            cursor_in_map_pos.with_x(x - map_width),
+            cursor_in_map_pos.with_x(x + map_width),
+        ]
+    } else {
+        vec![cursor_in_map_pos]
+    };
     // Once we have a world position we can transform it into a possible tile
     // position.
-    if let Some(tile_pos) = TilePos::from_world_pos(
-        &cursor_in_map_pos,
-        map_size,
-        grid_size,
-        tile_size,
-        map_type,
-        map_anchor,
-    ) {
+    if let Some(tile_pos) = cursor_in_map_positions
+        .iter()
+        .find_map(|cursor_in_map_pos| {
+            TilePos::from_world_pos(
+                cursor_in_map_pos,
+                map_size,
+                grid_size,
+                tile_size,
+                map_type,
+                map_anchor,
+            )
+        })
+    {
         commands.insert_resource(CursorTilePos(tile_pos));
     } else {
         // Cursor is not hovering over any tile.
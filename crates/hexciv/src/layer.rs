@@ -7,12 +7,24 @@ pub struct BaseTerrainLayer;
 #[derive(Component)]
 pub struct RiverLayer;
 
+#[derive(Component)]
+pub struct CliffLayer;
+
 #[derive(Component)]
 pub struct TerrainFeaturesLayer;
 
+#[derive(Component)]
+pub struct ResourceLayer;
+
+#[derive(Component)]
+pub struct FogOfWarLayer;
+
 #[derive(Component)]
 pub struct UnitSelectionLayer;
 
+#[derive(Component)]
+pub struct MovementPathLayer;
+
 #[derive(Component)]
 pub struct UnitStateLayer;
 
@@ -26,8 +38,12 @@ pub struct LandMilitaryUnitLayer;
 pub struct BaseTerrainLayerFilter(
     With<BaseTerrainLayer>,
     Without<RiverLayer>,
+    Without<CliffLayer>,
     Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
     Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
     Without<UnitStateLayer>,
     Without<CivilianUnitLayer>,
     Without<LandMilitaryUnitLayer>,
@@ -37,8 +53,27 @@ pub struct BaseTerrainLayerFilter(
 pub struct RiverLayerFilter(
     With<RiverLayer>,
     Without<BaseTerrainLayer>,
+    Without<CliffLayer>,
+    Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
+    Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
+    Without<UnitStateLayer>,
+    Without<CivilianUnitLayer>,
+    Without<LandMilitaryUnitLayer>,
+);
+
+#[derive(QueryFilter)]
+pub struct CliffLayerFilter(
+    With<CliffLayer>,
+    Without<BaseTerrainLayer>,
+    Without<RiverLayer>,
     Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
     Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
     Without<UnitStateLayer>,
     Without<CivilianUnitLayer>,
     Without<LandMilitaryUnitLayer>,
@@ -49,7 +84,41 @@ pub struct TerrainFeaturesLayerFilter(
     With<TerrainFeaturesLayer>,
     Without<BaseTerrainLayer>,
     Without<RiverLayer>,
+    Without<CliffLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
+    Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
+    Without<UnitStateLayer>,
+    Without<CivilianUnitLayer>,
+    Without<LandMilitaryUnitLayer>,
+);
+
+#[derive(QueryFilter)]
+pub struct ResourceLayerFilter(
+    With<ResourceLayer>,
+    Without<BaseTerrainLayer>,
+    Without<RiverLayer>,
+    Without<CliffLayer>,
+    Without<TerrainFeaturesLayer>,
+    Without<FogOfWarLayer>,
+    Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
+    Without<UnitStateLayer>,
+    Without<CivilianUnitLayer>,
+    Without<LandMilitaryUnitLayer>,
+);
+
+#[derive(QueryFilter)]
+pub struct FogOfWarLayerFilter(
+    With<FogOfWarLayer>,
+    Without<BaseTerrainLayer>,
+    Without<RiverLayer>,
+    Without<CliffLayer>,
+    Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
     Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
     Without<UnitStateLayer>,
     Without<CivilianUnitLayer>,
     Without<LandMilitaryUnitLayer>,
@@ -60,7 +129,26 @@ pub struct UnitSelectionLayerFilter(
     With<UnitSelectionLayer>,
     Without<BaseTerrainLayer>,
     Without<RiverLayer>,
+    Without<CliffLayer>,
+    Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
+    Without<MovementPathLayer>,
+    Without<UnitStateLayer>,
+    Without<CivilianUnitLayer>,
+    Without<LandMilitaryUnitLayer>,
+);
+
+#[derive(QueryFilter)]
+pub struct MovementPathLayerFilter(
+    With<MovementPathLayer>,
+    Without<BaseTerrainLayer>,
+    Without<RiverLayer>,
+    Without<CliffLayer>,
     Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
+    Without<UnitSelectionLayer>,
     Without<UnitStateLayer>,
     Without<CivilianUnitLayer>,
     Without<LandMilitaryUnitLayer>,
@@ -71,8 +159,12 @@ pub struct UnitStateLayerFilter(
     With<UnitStateLayer>,
     Without<BaseTerrainLayer>,
     Without<RiverLayer>,
+    Without<CliffLayer>,
     Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
     Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
     Without<CivilianUnitLayer>,
     Without<LandMilitaryUnitLayer>,
 );
@@ -82,8 +174,12 @@ pub struct CivilianUnitLayerFilter(
     With<CivilianUnitLayer>,
     Without<BaseTerrainLayer>,
     Without<RiverLayer>,
+    Without<CliffLayer>,
     Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
     Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
     Without<UnitStateLayer>,
     Without<LandMilitaryUnitLayer>,
 );
@@ -93,8 +189,12 @@ pub struct LandMilitaryUnitLayerFilter(
     With<LandMilitaryUnitLayer>,
     Without<BaseTerrainLayer>,
     Without<RiverLayer>,
+    Without<CliffLayer>,
     Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
     Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
     Without<UnitStateLayer>,
     Without<CivilianUnitLayer>,
 );
@@ -104,8 +204,12 @@ pub struct UnitLayersFilter(
     Or<(With<CivilianUnitLayer>, With<LandMilitaryUnitLayer>)>,
     Without<BaseTerrainLayer>,
     Without<RiverLayer>,
+    Without<CliffLayer>,
     Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
     Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
     Without<UnitStateLayer>,
 );
 
@@ -121,14 +225,30 @@ impl LayerZIndex for RiverLayer {
     const Z_INDEX: f32 = 1.0;
 }
 
+impl LayerZIndex for CliffLayer {
+    const Z_INDEX: f32 = 1.5;
+}
+
 impl LayerZIndex for TerrainFeaturesLayer {
     const Z_INDEX: f32 = 2.0;
 }
 
+impl LayerZIndex for ResourceLayer {
+    const Z_INDEX: f32 = 2.5;
+}
+
+impl LayerZIndex for FogOfWarLayer {
+    const Z_INDEX: f32 = 3.0;
+}
+
 impl LayerZIndex for UnitSelectionLayer {
     const Z_INDEX: f32 = 4.0;
 }
 
+impl LayerZIndex for MovementPathLayer {
+    const Z_INDEX: f32 = 4.5;
+}
+
 impl LayerZIndex for UnitStateLayer {
     const Z_INDEX: f32 = 5.0;
 }
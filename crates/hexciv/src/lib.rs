@@ -3,12 +3,19 @@ pub mod asset;
 pub mod civilization;
 #[cfg(debug_assertions)]
 pub mod dev_tools;
+pub mod era;
+pub mod fog_of_war;
 pub mod game_setup;
 pub mod input;
 pub mod input_dialog;
 pub mod layer;
+pub mod loading_screen;
+pub mod map;
+#[cfg(feature = "mapgen")]
+pub mod map_image;
 pub mod peer;
 pub mod player;
+pub mod resource;
 pub mod state;
 pub mod terrain;
 pub mod turn;
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use fastlem_random_terrain::Stage;
+
+use crate::asset::FontHandle;
+use crate::terrain::MapTerrainGeneration;
+
+#[derive(Component)]
+pub struct LoadingScreen;
+
+#[derive(Component)]
+pub struct LoadingScreenText;
+
+pub fn show_loading_screen(mut commands: Commands, font_handle: Res<FontHandle>) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            LoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Generating map..."),
+                TextFont {
+                    font: font_handle.0.clone(),
+                    font_size: 32.0,
+                    ..Default::default()
+                },
+                TextColor(Srgba::hex("#5C3F21").unwrap().into()),
+                TextLayout::new_with_justify(JustifyText::Center),
+                LoadingScreenText,
+            ));
+        });
+}
+
+/// Shows the progress of the terrain generation on the loading screen, or the
+/// error if it has failed.
+pub fn update_loading_screen(
+    map_terrain_generation: Res<MapTerrainGeneration>,
+    loading_screen_text_query: Single<(&mut Text,), With<LoadingScreenText>>,
+) {
+    let (mut loading_screen_text,) = loading_screen_text_query.into_inner();
+
+    let text = if let Some(err) = map_terrain_generation.error() {
+        format!("Failed to generate map:\n{err}\n\n[Esc] Cancel")
+    } else if map_terrain_generation.stage() == Some(Stage::Generating) {
+        // The last stage is run in a single step, which cannot be interrupted,
+        // so there is no progress to show until it is done. Where the steps are
+        // run on the main thread, such as on wasm, the screen also freezes.
+        format!(
+            "Generating map...\n{stage}...\n\nThis last stage can take a while, and cannot \
+             be cancelled part way through.\n\n[Esc] Cancel",
+            stage = Stage::Generating
+        )
+    } else {
+        let stage = map_terrain_generation
+            .stage()
+            .map_or_else(|| "done".to_owned(), |stage| stage.to_string());
+        format!(
+            "Generating map...\n{stage} ({progress:.0}%)\n\n[Esc] Cancel",
+            progress = map_terrain_generation.progress() * 100.0
+        )
+    };
+    if loading_screen_text.0 != text {
+        loading_screen_text.0 = text;
+    }
+}
+
+pub fn hide_loading_screen(
+    mut commands: Commands,
+    loading_screen_query: Single<(Entity,), With<LoadingScreen>>,
+) {
+    let (loading_screen_entity,) = loading_screen_query.into_inner();
+
+    commands.entity(loading_screen_entity).despawn();
+}
@@ -0,0 +1,166 @@
+use std::ops::{Index, IndexMut};
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use bitvec::prelude::*;
+use fastlem_random_terrain::TerrainGenError;
+use itertools::Itertools as _;
+use ordered_float::NotNan;
+
+use crate::game_setup::{MapRng, MapScript, MapSize, MapWrapping, NumPlayers};
+use crate::resource::generate_bonus_resources;
+use crate::terrain::{
+    BaseTerrain, CliffHexEdges, ElevationClassifierConfig, RiverHexEdges, TerrainFeatures,
+    choose_starting_positions, finish_terrain_generation, post_spawn_tilemap, spawn_tilemap,
+    start_terrain_generation,
+};
+
+/// The map, as the source of truth for the terrain of every tile.
+///
+/// This is independent of how the map is rendered. The tilemap layers are
+/// derived from it.
+#[derive(Clone, Debug, Resource)]
+pub struct GameMap {
+    size: TilemapSize,
+    /// The tiles in row-major order, starting from the tile at `(0, 0)`.
+    tiles: Vec<Tile>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Tile {
+    pub base_terrain: BaseTerrain,
+    pub terrain_features: Option<TerrainFeatures>,
+    /// The hex edges with a river, in the order of
+    /// [`HEX_DIRECTIONS`][bevy_ecs_tilemap::helpers::hex_grid::neighbors::HEX_DIRECTIONS].
+    pub river_hex_edges: RiverHexEdges,
+    /// The hex edges with cliffs, in the order of
+    /// [`HEX_DIRECTIONS`][bevy_ecs_tilemap::helpers::hex_grid::neighbors::HEX_DIRECTIONS].
+    pub cliff_hex_edges: CliffHexEdges,
+    pub elevation: NotNan<f64>,
+}
+
+/// Generates the map without rendering it, in a headless app.
+///
+/// The same systems which generate the map in the game are run, in the same
+/// order, so that the map is identical. The terrain is generated to completion
+/// without a loading screen, and the terrain tiles are not spawned, as the
+/// terrain can be read from the [`GameMap`].
+///
+/// Returns the error which the terrain generation has failed with, if it has
+/// been given up on.
+pub fn generate_headless_map(
+    map_seed: u64,
+    num_players: u8,
+    map_size: MapSize,
+    map_script: MapScript,
+    map_wrapping: MapWrapping,
+) -> Result<App, TerrainGenError> {
+    let mut app = App::new();
+    // The tilemap textures are loaded from the asset server, even though
+    // nothing is rendered.
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        ImagePlugin::default(),
+    ))
+    .insert_resource(MapRng(fastrand::Rng::with_seed(map_seed)))
+    .insert_resource(NumPlayers(num_players))
+    .insert_resource(map_size)
+    .insert_resource(map_script)
+    .insert_resource(map_wrapping)
+    .init_resource::<ElevationClassifierConfig>();
+
+    let world = app.world_mut();
+    world
+        .run_system_cached(start_terrain_generation)
+        .expect("starting the terrain generation should not fail");
+    world
+        .run_system_cached(finish_terrain_generation)
+        .expect("the terrain generation should have been started")?;
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(
+        (
+            spawn_tilemap,
+            post_spawn_tilemap,
+            choose_starting_positions,
+            generate_bonus_resources,
+        )
+            .chain(),
+    );
+    schedule.run(app.world_mut());
+
+    Ok(app)
+}
+
+impl GameMap {
+    /// Creates a map of the given size, with each tile created by calling `f`
+    /// with its position.
+    pub fn from_fn<F>(size: TilemapSize, mut f: F) -> Self
+    where
+        F: FnMut(&TilePos) -> Tile,
+    {
+        let tiles = (0..size.y)
+            .cartesian_product(0..size.x)
+            .map(|(y, x)| f(&TilePos { x, y }))
+            .collect();
+        Self { size, tiles }
+    }
+
+    pub fn size(&self) -> TilemapSize {
+        self.size
+    }
+
+    pub fn get(&self, tile_pos: &TilePos) -> Option<&Tile> {
+        self.index_of(tile_pos).map(|i| &self.tiles[i])
+    }
+
+    pub fn get_mut(&mut self, tile_pos: &TilePos) -> Option<&mut Tile> {
+        self.index_of(tile_pos).map(|i| &mut self.tiles[i])
+    }
+
+    /// Returns an iterator over the tiles in row-major order, along with their
+    /// positions.
+    pub fn iter(&self) -> impl Iterator<Item = (TilePos, &Tile)> {
+        (0..self.size.y)
+            .cartesian_product(0..self.size.x)
+            .map(|(y, x)| TilePos { x, y })
+            .zip(&self.tiles)
+    }
+
+    fn index_of(&self, tile_pos: &TilePos) -> Option<usize> {
+        tile_pos
+            .within_map_bounds(&self.size)
+            .then(|| tile_pos.to_index(&self.size))
+    }
+}
+
+impl Index<&TilePos> for GameMap {
+    type Output = Tile;
+
+    fn index(&self, tile_pos: &TilePos) -> &Self::Output {
+        self.get(tile_pos)
+            .expect("tile position should be within the map bounds")
+    }
+}
+
+impl IndexMut<&TilePos> for GameMap {
+    fn index_mut(&mut self, tile_pos: &TilePos) -> &mut Self::Output {
+        self.get_mut(tile_pos)
+            .expect("tile position should be within the map bounds")
+    }
+}
+
+impl Tile {
+    /// Creates a tile with the base terrain and elevation, without any terrain
+    /// features, rivers or cliffs.
+    pub fn new(base_terrain: BaseTerrain, elevation: NotNan<f64>) -> Self {
+        Self {
+            base_terrain,
+            terrain_features: None,
+            river_hex_edges: BitArray::<_>::ZERO,
+            cliff_hex_edges: BitArray::<_>::ZERO,
+            elevation,
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::helpers::hex_grid::axial::AxialPos;
+use bevy_ecs_tilemap::helpers::hex_grid::neighbors::HEX_DIRECTIONS;
+use bevy_ecs_tilemap::helpers::hex_grid::offset::RowOddPos;
+use bevy_ecs_tilemap::prelude::*;
+use image::{Rgba, RgbaImage};
+use itertools::Itertools as _;
+
+use crate::terrain::{BaseTerrain, RiverHexEdges, TerrainFeatures};
+
+/// The half-width of the rivers, as a fraction of the distance from the center
+/// of a tile to its edges.
+const RIVER_HALF_WIDTH: f32 = 0.15;
+/// The size of the terrain features drawn in the middle of a tile, as a
+/// fraction of the size of the tile.
+const TERRAIN_FEATURES_SIZE: f32 = 0.5;
+/// The range of the factor by which the land tiles are shaded by their
+/// elevation, from the lowest to the highest elevation.
+const ELEVATION_SHADING: (f32, f32) = (0.75, 1.25);
+
+const RIVER_COLOR: Rgba<u8> = Rgba([40, 110, 220, 255]);
+
+struct TileColors {
+    base_terrain: Rgba<u8>,
+    terrain_features: Option<Rgba<u8>>,
+    river_hex_edges: RiverHexEdges,
+}
+
+/// Renders an overview image of the map on the CPU, with one colored hex per
+/// tile, `tile_width` pixels wide.
+///
+/// The land tiles are shaded by their elevation, if `tile_elevation` is given.
+pub fn render_map_image<B, F, R, E>(
+    map_size: &TilemapSize,
+    tile_width: u32,
+    base_terrain: B,
+    terrain_features: F,
+    river_hex_edges: R,
+    tile_elevation: Option<E>,
+) -> RgbaImage
+where
+    B: Fn(&TilePos) -> BaseTerrain,
+    F: Fn(&TilePos) -> Option<TerrainFeatures>,
+    R: Fn(&TilePos) -> RiverHexEdges,
+    E: Fn(&TilePos) -> f64,
+{
+    // The tiles are regular hexagons, as in the game.
+    let grid_size = TilemapGridSize {
+        x: tile_width as f32,
+        y: tile_width as f32 * 2.0 / 3.0_f32.sqrt(),
+    };
+
+    let tile_positions: Vec<_> = (0..map_size.y)
+        .cartesian_product(0..map_size.x)
+        .map(|(y, x)| TilePos { x, y })
+        .collect();
+
+    let max_elevation = tile_elevation.as_ref().map(|tile_elevation| {
+        tile_positions
+            .iter()
+            .map(tile_elevation)
+            .fold(f64::EPSILON, f64::max)
+    });
+    let tile_colors: Vec<_> = tile_positions
+        .iter()
+        .map(|tile_pos| {
+            let base_terrain = base_terrain(tile_pos);
+            let mut base_terrain_color = base_terrain_color(base_terrain);
+            if let (Some(tile_elevation), Some(max_elevation)) = (&tile_elevation, max_elevation) {
+                if !base_terrain.is_water() {
+                    let (min_shading, max_shading) = ELEVATION_SHADING;
+                    let t = (tile_elevation(tile_pos) / max_elevation)
+                        .clamp(0.0, 1.0)
+                        .sqrt() as f32;
+                    base_terrain_color = shade(
+                        base_terrain_color,
+                        min_shading + (max_shading - min_shading) * t,
+                    );
+                }
+            }
+            TileColors {
+                base_terrain: base_terrain_color,
+                terrain_features: terrain_features(tile_pos).map(terrain_features_color),
+                river_hex_edges: river_hex_edges(tile_pos),
+            }
+        })
+        .collect();
+
+    let tile_center = |tile_pos: &TilePos| {
+        AxialPos::from_tile_pos_given_coord_system(tile_pos, HexCoordSystem::RowOdd)
+            .center_in_world_row(&grid_size)
+    };
+    let (min, max) = tile_positions.iter().map(tile_center).fold(
+        (Vec2::INFINITY, Vec2::NEG_INFINITY),
+        |(min, max), center| (min.min(center), max.max(center)),
+    );
+    let min = min - Vec2::new(grid_size.x, grid_size.y) / 2.0;
+    let max = max + Vec2::new(grid_size.x, grid_size.y) / 2.0;
+
+    // The vectors from the center of a tile to the centers of its neighbors.
+    let neighbor_offsets =
+        HEX_DIRECTIONS.map(|direction| AxialPos::from(direction).center_in_world_row(&grid_size));
+
+    let mut image = RgbaImage::new((max.x - min.x).ceil() as u32, (max.y - min.y).ceil() as u32);
+    for (px, py, pixel) in image.enumerate_pixels_mut() {
+        // The image is drawn with north up, as in the game.
+        let world_pos = Vec2::new(min.x + px as f32 + 0.5, max.y - py as f32 - 0.5);
+        let axial_pos = AxialPos::from_world_pos_row(&world_pos, &grid_size);
+        let RowOddPos { q, r } = RowOddPos::from(axial_pos);
+        let (Ok(x), Ok(y)) = (u32::try_from(q), u32::try_from(r)) else {
+            continue;
+        };
+        if x >= map_size.x || y >= map_size.y {
+            continue;
+        }
+        let tile_colors = &tile_colors[(y * map_size.x + x) as usize];
+
+        // The distance from the center of the tile towards each of its edges, as a
+        // fraction of the distance to the edge.
+        let offset = world_pos - axial_pos.center_in_world_row(&grid_size);
+        let edge_distances = neighbor_offsets.map(|neighbor_offset| {
+            2.0 * offset.dot(neighbor_offset) / neighbor_offset.length_squared()
+        });
+
+        let is_river = edge_distances
+            .iter()
+            .zip(tile_colors.river_hex_edges)
+            .any(|(&distance, is_river)| is_river && distance > 1.0 - RIVER_HALF_WIDTH);
+        let is_terrain_features = edge_distances
+            .iter()
+            .all(|&distance| distance < TERRAIN_FEATURES_SIZE);
+        *pixel = match tile_colors.terrain_features {
+            _ if is_river => RIVER_COLOR,
+            Some(terrain_features_color) if is_terrain_features => terrain_features_color,
+            _ => tile_colors.base_terrain,
+        };
+    }
+
+    image
+}
+
+fn base_terrain_color(base_terrain: BaseTerrain) -> Rgba<u8> {
+    match base_terrain {
+        BaseTerrain::Plains => Rgba([196, 188, 104, 255]),
+        BaseTerrain::Grassland => Rgba([110, 164, 66, 255]),
+        BaseTerrain::Desert => Rgba([230, 208, 144, 255]),
+        BaseTerrain::Tundra => Rgba([156, 146, 122, 255]),
+        BaseTerrain::Snow => Rgba([236, 238, 244, 255]),
+        BaseTerrain::PlainsHills => Rgba([164, 150, 80, 255]),
+        BaseTerrain::GrasslandHills => Rgba([84, 132, 50, 255]),
+        BaseTerrain::DesertHills => Rgba([198, 170, 110, 255]),
+        BaseTerrain::TundraHills => Rgba([126, 116, 96, 255]),
+        BaseTerrain::SnowHills => Rgba([204, 208, 218, 255]),
+        BaseTerrain::PlainsMountains => Rgba([124, 110, 84, 255]),
+        BaseTerrain::GrasslandMountains => Rgba([100, 100, 80, 255]),
+        BaseTerrain::DesertMountains => Rgba([146, 120, 90, 255]),
+        BaseTerrain::TundraMountains => Rgba([100, 92, 84, 255]),
+        BaseTerrain::SnowMountains => Rgba([170, 170, 180, 255]),
+        BaseTerrain::Coast => Rgba([88, 160, 210, 255]),
+        BaseTerrain::Ocean => Rgba([30, 70, 140, 255]),
+        BaseTerrain::Lake => Rgba([70, 140, 220, 255]),
+    }
+}
+
+fn terrain_features_color(terrain_features: TerrainFeatures) -> Rgba<u8> {
+    match terrain_features {
+        TerrainFeatures::Woods => Rgba([34, 90, 34, 255]),
+        TerrainFeatures::Rainforest => Rgba([16, 110, 76, 255]),
+        TerrainFeatures::Marsh => Rgba([84, 112, 84, 255]),
+        TerrainFeatures::Floodplains => Rgba([176, 150, 70, 255]),
+        TerrainFeatures::Oasis => Rgba([60, 176, 200, 255]),
+        TerrainFeatures::Cliffs => Rgba([90, 80, 70, 255]),
+        TerrainFeatures::Ice => Rgba([220, 240, 255, 255]),
+    }
+}
+
+fn shade(Rgba([r, g, b, a]): Rgba<u8>, factor: f32) -> Rgba<u8> {
+    let shade_channel = |channel: u8| (f32::from(channel) * factor).round().clamp(0.0, 255.0) as u8;
+    Rgba([shade_channel(r), shade_channel(g), shade_channel(b), a])
+}
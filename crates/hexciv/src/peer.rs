@@ -5,9 +5,12 @@ use bevy::prelude::*;
 use bevy_matchbox::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::game_setup::{GameRng, GameSessionId, GameSetup, MapRng, NumPlayers};
+use crate::game_setup::{
+    GameRng, GameSessionId, GameSetup, MapRng, MapScript, MapSize, MapWrapping, NumPlayers,
+};
 use crate::player::{PlayerIndex, init_our_player};
 use crate::state::{GameState, MultiplayerState};
+use crate::terrain::ElevationClassifierConfig;
 use crate::turn::TurnStarted;
 use crate::unit::{ActionsLegend, UnitMoved, UnitSpawned};
 
@@ -116,10 +119,13 @@ pub fn wait_for_peers(
     map_rng: Option<Res<MapRng>>,
     game_rng: Option<Res<GameRng>>,
     num_players: Option<Res<NumPlayers>>,
+    map_size: Option<Res<MapSize>>,
+    map_script: Option<Res<MapScript>>,
+    map_wrapping: Option<Res<MapWrapping>>,
+    elevation_classifier_config: Res<ElevationClassifierConfig>,
     multiplayer_state: Res<State<MultiplayerState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
     actions_legend_text_query: Single<(&mut Text,), With<ActionsLegend>>,
-    mut peer_connected_events: EventWriter<PeerConnected>,
 ) {
     let (mut actions_legend_text,) = actions_legend_text_query.into_inner();
 
@@ -153,6 +159,10 @@ pub fn wait_for_peers(
                 map_seed: map_rng.expect("map_rng should not be None").0.get_seed(),
                 game_seed: game_rng.expect("game_rng should not be None").0.get_seed(),
                 num_players: num_players.expect("num_players should not be None").0,
+                map_size: *map_size.expect("map_size should not be None"),
+                map_script: *map_script.expect("map_script should not be None"),
+                map_wrapping: *map_wrapping.expect("map_wrapping should not be None"),
+                elevation_classifier_config: *elevation_classifier_config,
             };
             debug!(
                 ?game_setup,
@@ -166,12 +176,6 @@ pub fn wait_for_peers(
             for &peer_id in &peers {
                 channel.send(game_setup_message.clone().into(), peer_id);
             }
-            for (i, peer_id) in iter::once(host_id).chain(peers).enumerate() {
-                peer_connected_events.write(PeerConnected {
-                    peer_id,
-                    player_index: i.try_into().unwrap(),
-                });
-            }
             host_id
         },
         MultiplayerState::Joining => {
@@ -189,10 +193,18 @@ pub fn wait_for_peers(
                 map_seed,
                 game_seed,
                 num_players,
+                map_size,
+                map_script,
+                map_wrapping,
+                elevation_classifier_config,
             } = game_setup;
             commands.insert_resource(MapRng(fastrand::Rng::with_seed(map_seed)));
             commands.insert_resource(GameRng(fastrand::Rng::with_seed(game_seed)));
             commands.insert_resource(NumPlayers(num_players));
+            commands.insert_resource(map_size);
+            commands.insert_resource(map_script);
+            commands.insert_resource(map_wrapping);
+            commands.insert_resource(elevation_classifier_config);
             let (host_id, _) = socket_rx_queue.0.pop_front().unwrap();
             host_id
         },
@@ -204,7 +216,29 @@ pub fn wait_for_peers(
     commands.insert_resource(OurPeerId(our_peer_id));
     commands.insert_resource(HostId(host_id));
 
-    next_game_state.set(GameState::InGame);
+    next_game_state.set(GameState::Loading);
+}
+
+/// Writes [`PeerConnected`] events for the host and all connected peers,
+/// assigning their player indices.
+///
+/// This should be called on the host.
+pub fn announce_connected_peers(
+    socket: Res<MatchboxSocket>,
+    our_peer_id: Res<OurPeerId>,
+    host_id: Res<HostId>,
+    mut peer_connected_events: EventWriter<PeerConnected>,
+) {
+    assert!(our_peer_id.0 == host_id.0);
+    for (i, peer_id) in iter::once(host_id.0)
+        .chain(socket.connected_peers())
+        .enumerate()
+    {
+        peer_connected_events.write(PeerConnected {
+            peer_id,
+            player_index: i.try_into().unwrap(),
+        });
+    }
 }
 
 /// Sends [`HostBroadcast`] events to all connected peers.
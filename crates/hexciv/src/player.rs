@@ -4,6 +4,8 @@ use bevy::prelude::*;
 use strum::VariantArray as _;
 
 use crate::civilization::Civilization;
+use crate::era::PlayerEra;
+use crate::fog_of_war::PlayerVisibility;
 use crate::game_setup::{GameRng, NumPlayers};
 use crate::peer::{OurPeerId, Peer};
 
@@ -11,7 +13,7 @@ use crate::peer::{OurPeerId, Peer};
 pub struct OurPlayer(pub Entity);
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Component)]
-#[require(PlayerState)]
+#[require(PlayerCapabilities, PlayerEra, PlayerState, PlayerVisibility)]
 pub struct Player {
     pub player_index: PlayerIndex,
     pub civ: Civilization,
@@ -20,6 +22,16 @@ pub struct Player {
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct PlayerIndex(pub u8);
 
+/// What the units of a player are capable of.
+///
+/// These are to be unlocked by researching techs. Until then, every player
+/// starts with all of them.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Component)]
+pub struct PlayerCapabilities {
+    /// Whether land units can embark onto water tiles.
+    pub can_embark: bool,
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Component)]
 pub enum PlayerState {
     #[default]
@@ -27,6 +39,12 @@ pub enum PlayerState {
     WaitingForTurnEnd,
 }
 
+impl Default for PlayerCapabilities {
+    fn default() -> Self {
+        Self { can_embark: true }
+    }
+}
+
 impl Ord for Player {
     fn cmp(&self, other: &Self) -> Ordering {
         self.player_index.cmp(&other.player_index)
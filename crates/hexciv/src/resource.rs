@@ -0,0 +1,557 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use itertools::{Itertools as _, chain};
+use serde::Serialize;
+use strum::VariantArray;
+
+use crate::era::{Era, PlayerEra};
+use crate::game_setup::{MapRng, MapWrapping};
+use crate::layer::{ResourceLayer, ResourceLayerFilter};
+use crate::map::{GameMap, Tile};
+use crate::player::{OurPlayer, Player};
+use crate::terrain::{BaseTerrain, StartingPositions, TerrainFeatures, hex_distance};
+
+/// The minimum distance between any two bonus resources.
+const BONUS_RESOURCE_MIN_DISTANCE: u32 = 2;
+/// The radius of the starting area around each starting position.
+pub const STARTING_AREA_RADIUS: u32 = 3;
+/// The minimum number of bonus resources in each starting area.
+pub const STARTING_AREA_MIN_BONUS_RESOURCES: usize = 3;
+/// The maximum number of bonus resources in each starting area.
+pub const STARTING_AREA_MAX_BONUS_RESOURCES: usize = 5;
+
+const BONUS_RESOURCE_CHOICES: [bool; 3] = [true, false, false];
+
+/// The minimum distance between any two strategic resources.
+const STRATEGIC_RESOURCE_MIN_DISTANCE: u32 = 2;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, VariantArray)]
+pub enum BonusResource {
+    Bananas,
+    Cattle,
+    Copper,
+    Deer,
+    Fish,
+    Rice,
+    Sheep,
+    Stone,
+    Wheat,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, VariantArray)]
+pub enum StrategicResource {
+    Horses,
+    Iron,
+    Niter,
+    Coal,
+    Oil,
+    Aluminum,
+    Uranium,
+}
+
+/// A resource on a tile, as shown on the resource layer.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+enum TileResource {
+    Bonus(BonusResource),
+    Strategic(StrategicResource),
+}
+
+/// The bonus resources on the map.
+#[derive(Debug, Resource)]
+pub struct MapBonusResources(BTreeMap<TilePos, BonusResource>);
+
+/// The strategic resources on the map, including those which have not been
+/// revealed to any player yet.
+#[derive(Debug, Resource)]
+pub struct MapStrategicResources(BTreeMap<TilePos, StrategicResource>);
+
+impl BonusResource {
+    /// Returns whether the bonus resource can be found on the terrain.
+    pub fn is_found_on(
+        &self,
+        base_terrain: BaseTerrain,
+        terrain_features: Option<TerrainFeatures>,
+    ) -> bool {
+        match self {
+            Self::Bananas => terrain_features == Some(TerrainFeatures::Rainforest),
+            Self::Cattle => base_terrain == BaseTerrain::Grassland && terrain_features.is_none(),
+            Self::Copper => base_terrain.is_hills() && terrain_features.is_none(),
+            Self::Deer => {
+                terrain_features == Some(TerrainFeatures::Woods)
+                    || ([BaseTerrain::Tundra, BaseTerrain::TundraHills].contains(&base_terrain)
+                        && terrain_features.is_none())
+            },
+            Self::Fish => {
+                [BaseTerrain::Coast, BaseTerrain::Lake].contains(&base_terrain)
+                    && terrain_features.is_none()
+            },
+            Self::Rice => {
+                base_terrain == BaseTerrain::Grassland
+                    && matches!(terrain_features, None | Some(TerrainFeatures::Marsh))
+            },
+            Self::Sheep => {
+                [
+                    BaseTerrain::PlainsHills,
+                    BaseTerrain::GrasslandHills,
+                    BaseTerrain::DesertHills,
+                    BaseTerrain::TundraHills,
+                ]
+                .contains(&base_terrain)
+                    && terrain_features.is_none()
+            },
+            Self::Stone => {
+                [
+                    BaseTerrain::Plains,
+                    BaseTerrain::PlainsHills,
+                    BaseTerrain::Grassland,
+                    BaseTerrain::GrasslandHills,
+                    BaseTerrain::Desert,
+                    BaseTerrain::DesertHills,
+                ]
+                .contains(&base_terrain)
+                    && terrain_features.is_none()
+            },
+            Self::Wheat => {
+                (base_terrain == BaseTerrain::Plains
+                    && matches!(terrain_features, None | Some(TerrainFeatures::Floodplains)))
+                    || (base_terrain == BaseTerrain::Desert
+                        && terrain_features == Some(TerrainFeatures::Floodplains))
+            },
+        }
+    }
+}
+
+impl StrategicResource {
+    /// Returns the era in which the strategic resource is revealed to a
+    /// player, roughly following the era of the tech which reveals it in Civ
+    /// VI.
+    pub fn reveal_era(&self) -> Era {
+        match self {
+            Self::Horses | Self::Iron => Era::Ancient,
+            Self::Niter => Era::Medieval,
+            Self::Coal => Era::Industrial,
+            Self::Oil | Self::Aluminum => Era::Modern,
+            Self::Uranium => Era::Atomic,
+        }
+    }
+
+    pub fn is_revealed_to(&self, player_era: &PlayerEra) -> bool {
+        player_era.0 >= self.reveal_era()
+    }
+
+    /// Returns the chance (in percent) of the strategic resource being placed
+    /// on a tile it can be found on.
+    fn frequency(&self) -> u32 {
+        match self {
+            Self::Horses => 8,
+            Self::Iron | Self::Niter | Self::Coal | Self::Oil | Self::Aluminum => 6,
+            Self::Uranium => 4,
+        }
+    }
+
+    /// Returns whether the strategic resource can be found on the terrain.
+    pub fn is_found_on(
+        &self,
+        base_terrain: BaseTerrain,
+        terrain_features: Option<TerrainFeatures>,
+    ) -> bool {
+        match self {
+            Self::Horses => {
+                [BaseTerrain::Plains, BaseTerrain::Grassland].contains(&base_terrain)
+                    && matches!(terrain_features, None | Some(TerrainFeatures::Floodplains))
+            },
+            Self::Iron => {
+                base_terrain.is_hills()
+                    && matches!(terrain_features, None | Some(TerrainFeatures::Woods))
+            },
+            Self::Niter => {
+                [
+                    BaseTerrain::Plains,
+                    BaseTerrain::Grassland,
+                    BaseTerrain::Desert,
+                    BaseTerrain::Tundra,
+                ]
+                .contains(&base_terrain)
+                    && matches!(terrain_features, None | Some(TerrainFeatures::Floodplains))
+            },
+            Self::Coal => {
+                (matches!(
+                    terrain_features,
+                    Some(TerrainFeatures::Woods | TerrainFeatures::Rainforest)
+                )) || ([BaseTerrain::PlainsHills, BaseTerrain::GrasslandHills]
+                    .contains(&base_terrain)
+                    && terrain_features.is_none())
+            },
+            Self::Oil => {
+                matches!(
+                    terrain_features,
+                    Some(TerrainFeatures::Marsh | TerrainFeatures::Rainforest)
+                ) || ([
+                    BaseTerrain::Desert,
+                    BaseTerrain::Tundra,
+                    BaseTerrain::Snow,
+                    BaseTerrain::Coast,
+                ]
+                .contains(&base_terrain)
+                    && terrain_features.is_none())
+            },
+            Self::Aluminum => {
+                terrain_features == Some(TerrainFeatures::Rainforest)
+                    || ([
+                        BaseTerrain::Plains,
+                        BaseTerrain::PlainsHills,
+                        BaseTerrain::Desert,
+                        BaseTerrain::DesertHills,
+                    ]
+                    .contains(&base_terrain)
+                        && terrain_features.is_none())
+            },
+            Self::Uranium => {
+                !base_terrain.is_water()
+                    && !base_terrain.is_mountains()
+                    && !matches!(
+                        terrain_features,
+                        Some(TerrainFeatures::Oasis | TerrainFeatures::Floodplains)
+                    )
+            },
+        }
+    }
+}
+
+impl MapBonusResources {
+    pub fn get(&self, tile_pos: &TilePos) -> Option<BonusResource> {
+        self.0.get(tile_pos).copied()
+    }
+}
+
+impl MapStrategicResources {
+    pub fn new(strategic_resources: BTreeMap<TilePos, StrategicResource>) -> Self {
+        Self(strategic_resources)
+    }
+
+    /// Returns the strategic resource on the tile, whether or not it has been
+    /// revealed to any player.
+    ///
+    /// This must not be used for anything which is shown to a player.
+    pub fn get(&self, tile_pos: &TilePos) -> Option<StrategicResource> {
+        self.0.get(tile_pos).copied()
+    }
+
+    /// Returns the strategic resource on the tile, if it has been revealed to
+    /// the player.
+    ///
+    /// Anything which is shown to a player must go through this, so as to not
+    /// leak unrevealed strategic resources.
+    pub fn get_revealed(
+        &self,
+        tile_pos: &TilePos,
+        player_era: &PlayerEra,
+    ) -> Option<StrategicResource> {
+        self.0
+            .get(tile_pos)
+            .copied()
+            .filter(|strategic_resource| strategic_resource.is_revealed_to(player_era))
+    }
+}
+
+/// Generates strategic resources on the map, based on the final terrain.
+pub fn generate_strategic_resources<B, F>(
+    rng: &mut fastrand::Rng,
+    map_size: &TilemapSize,
+    map_wrapping: MapWrapping,
+    base_terrain: B,
+    terrain_features: F,
+) -> BTreeMap<TilePos, StrategicResource>
+where
+    B: Fn(&TilePos) -> BaseTerrain,
+    F: Fn(&TilePos) -> Option<TerrainFeatures>,
+{
+    let mut tile_positions: Vec<_> = (0..map_size.x)
+        .cartesian_product(0..map_size.y)
+        .map(|(x, y)| TilePos { x, y })
+        .collect();
+    rng.shuffle(&mut tile_positions);
+
+    let mut strategic_resources: BTreeMap<TilePos, StrategicResource> = BTreeMap::new();
+
+    for tile_pos in tile_positions {
+        if !strategic_resources.keys().all(|other_tile_pos| {
+            hex_distance(&tile_pos, other_tile_pos, map_size, map_wrapping)
+                >= STRATEGIC_RESOURCE_MIN_DISTANCE
+        }) {
+            continue;
+        }
+        let (base_terrain, terrain_features) =
+            (base_terrain(&tile_pos), terrain_features(&tile_pos));
+        for &strategic_resource in StrategicResource::VARIANTS {
+            if strategic_resource.is_found_on(base_terrain, terrain_features)
+                && rng.u32(..100) < strategic_resource.frequency()
+            {
+                strategic_resources.insert(tile_pos, strategic_resource);
+                break;
+            }
+        }
+    }
+
+    strategic_resources
+}
+
+/// Generates bonus resources on the map.
+///
+/// Bonus resources are kept apart from each other, and every starting area
+/// ends up with a similar number of them.
+pub fn generate_bonus_resources(
+    mut commands: Commands,
+    mut map_rng: ResMut<MapRng>,
+    starting_positions: Res<StartingPositions>,
+    map_wrapping: Res<MapWrapping>,
+    game_map: Res<GameMap>,
+    map_strategic_resources: Res<MapStrategicResources>,
+) {
+    let rng = &mut map_rng.0;
+    let map_wrapping = *map_wrapping;
+    let map_size = &game_map.size();
+
+    let possible_bonus_resources = |tile_pos: &TilePos| -> Vec<BonusResource> {
+        let Tile {
+            base_terrain,
+            terrain_features,
+            ..
+        } = game_map[tile_pos];
+        BonusResource::VARIANTS
+            .iter()
+            .copied()
+            .filter(|bonus_resource| bonus_resource.is_found_on(base_terrain, terrain_features))
+            .collect()
+    };
+    let is_spaced_out = |bonus_resources: &BTreeMap<TilePos, BonusResource>, tile_pos: &TilePos| {
+        bonus_resources.keys().all(|other_tile_pos| {
+            hex_distance(tile_pos, other_tile_pos, map_size, map_wrapping)
+                >= BONUS_RESOURCE_MIN_DISTANCE
+        })
+    };
+
+    let mut tile_positions: Vec<_> = (0..map_size.x)
+        .cartesian_product(0..map_size.y)
+        .map(|(x, y)| TilePos { x, y })
+        .collect();
+    rng.shuffle(&mut tile_positions);
+
+    let mut bonus_resources: BTreeMap<TilePos, BonusResource> = BTreeMap::new();
+
+    for tile_pos in &tile_positions {
+        if map_strategic_resources.get(tile_pos).is_some()
+            || !is_spaced_out(&bonus_resources, tile_pos)
+        {
+            continue;
+        }
+        let Some(bonus_resource) = rng.choice(possible_bonus_resources(tile_pos)) else {
+            continue;
+        };
+        if rng.choice(BONUS_RESOURCE_CHOICES).unwrap() {
+            bonus_resources.insert(*tile_pos, bonus_resource);
+        }
+    }
+
+    // Balance the number of bonus resources in each starting area.
+    //
+    // The starting areas may overlap, so a bonus resource is only removed if
+    // every other starting area it is in would still have enough, and only
+    // added if none of the starting areas it is in would then have too many.
+    let starting_areas_of = |tile_pos: &TilePos| -> Vec<usize> {
+        starting_positions
+            .0
+            .iter()
+            .positions(|starting_position| {
+                hex_distance(tile_pos, starting_position, map_size, map_wrapping)
+                    <= STARTING_AREA_RADIUS
+            })
+            .collect()
+    };
+    let area_positions: Vec<Vec<TilePos>> = starting_positions
+        .0
+        .iter()
+        .map(|starting_position| {
+            tile_positions
+                .iter()
+                .filter(|tile_pos| {
+                    hex_distance(tile_pos, starting_position, map_size, map_wrapping)
+                        <= STARTING_AREA_RADIUS
+                })
+                .copied()
+                .collect()
+        })
+        .collect();
+    let num_bonus_resources_in = |bonus_resources: &BTreeMap<TilePos, _>, area_index: usize| {
+        area_positions[area_index]
+            .iter()
+            .filter(|tile_pos| bonus_resources.contains_key(tile_pos))
+            .count()
+    };
+    let can_place = |bonus_resources: &BTreeMap<TilePos, BonusResource>, tile_pos: &TilePos| {
+        !starting_positions.0.contains(tile_pos)
+            && map_strategic_resources.get(tile_pos).is_none()
+            && is_spaced_out(bonus_resources, tile_pos)
+            && !possible_bonus_resources(tile_pos).is_empty()
+            && starting_areas_of(tile_pos).into_iter().all(|area_index| {
+                num_bonus_resources_in(bonus_resources, area_index)
+                    < STARTING_AREA_MAX_BONUS_RESOURCES
+            })
+    };
+
+    for (area_index, positions_in_area) in area_positions.iter().enumerate() {
+        for tile_pos in positions_in_area {
+            if num_bonus_resources_in(&bonus_resources, area_index)
+                <= STARTING_AREA_MAX_BONUS_RESOURCES
+            {
+                break;
+            }
+            if !bonus_resources.contains_key(tile_pos) {
+                continue;
+            }
+            if starting_areas_of(tile_pos)
+                .into_iter()
+                .all(|other_area_index| {
+                    other_area_index == area_index
+                        || num_bonus_resources_in(&bonus_resources, other_area_index)
+                            > STARTING_AREA_MIN_BONUS_RESOURCES
+                })
+            {
+                bonus_resources.remove(tile_pos);
+            }
+        }
+    }
+
+    for (area_index, positions_in_area) in area_positions.iter().enumerate() {
+        // Keep going over the starting area, so that the bonus resources are
+        // still placed with the same odds, until there are enough of them or
+        // no more can be placed.
+        loop {
+            let candidate_positions: Vec<_> = positions_in_area
+                .iter()
+                .filter(|tile_pos| can_place(&bonus_resources, tile_pos))
+                .copied()
+                .collect();
+            if candidate_positions.is_empty() {
+                break;
+            }
+            for tile_pos in &candidate_positions {
+                if num_bonus_resources_in(&bonus_resources, area_index)
+                    >= STARTING_AREA_MIN_BONUS_RESOURCES
+                {
+                    break;
+                }
+                if !can_place(&bonus_resources, tile_pos) {
+                    continue;
+                }
+                let bonus_resource = rng.choice(possible_bonus_resources(tile_pos)).unwrap();
+                if rng.choice(BONUS_RESOURCE_CHOICES).unwrap() {
+                    bonus_resources.insert(*tile_pos, bonus_resource);
+                }
+            }
+            if num_bonus_resources_in(&bonus_resources, area_index)
+                >= STARTING_AREA_MIN_BONUS_RESOURCES
+            {
+                break;
+            }
+        }
+    }
+
+    debug!(
+        num_bonus_resources = bonus_resources.len(),
+        "generated bonus resources"
+    );
+
+    commands.insert_resource(MapBonusResources(bonus_resources));
+}
+
+/// Spawns the tiles of the resource layer, derived from the resources on the
+/// map.
+pub fn spawn_resource_tiles(
+    mut commands: Commands,
+    map_bonus_resources: Res<MapBonusResources>,
+    map_strategic_resources: Res<MapStrategicResources>,
+    resource_tilemap_query: Single<(Entity, &mut TileStorage), ResourceLayerFilter>,
+) {
+    let (resource_tilemap_entity, mut resource_tile_storage) = resource_tilemap_query.into_inner();
+
+    let resources = chain(
+        map_bonus_resources
+            .0
+            .iter()
+            .map(|(&tile_pos, &bonus_resource)| (tile_pos, TileResource::Bonus(bonus_resource))),
+        map_strategic_resources
+            .0
+            .iter()
+            .map(|(&tile_pos, &strategic_resource)| {
+                (tile_pos, TileResource::Strategic(strategic_resource))
+            }),
+    );
+    for (tile_pos, resource) in resources {
+        let tile_entity = commands
+            .spawn(TileBundle {
+                position: tile_pos,
+                tilemap_id: TilemapId(resource_tilemap_entity),
+                texture_index: resource_texture_index(resource),
+                // Strategic resources are hidden until revealed to our player.
+                visible: TileVisible(matches!(resource, TileResource::Bonus(_))),
+                ..Default::default()
+            })
+            .insert(ResourceLayer)
+            .id();
+        resource_tile_storage.set(&tile_pos, tile_entity);
+    }
+}
+
+/// Shows / hides the strategic resources on the map, depending on whether
+/// they have been revealed to our player.
+pub fn update_strategic_resource_visibility(
+    our_player: Res<OurPlayer>,
+    map_strategic_resources: Res<MapStrategicResources>,
+    player_query: Query<(Ref<PlayerEra>,), With<Player>>,
+    mut resource_tile_query: Query<(Ref<TilePos>, &mut TileVisible), ResourceLayerFilter>,
+) {
+    let Ok((player_era,)) = player_query.get(our_player.0) else {
+        return;
+    };
+
+    for (tile_pos, mut tile_visible) in resource_tile_query.iter_mut() {
+        if !player_era.is_changed() && !tile_pos.is_added() {
+            continue;
+        }
+        if map_strategic_resources.get(&tile_pos).is_none() {
+            // Bonus resources are always revealed.
+            continue;
+        }
+        tile_visible.set_if_neq(TileVisible(
+            map_strategic_resources
+                .get_revealed(&tile_pos, &player_era)
+                .is_some(),
+        ));
+    }
+}
+
+/// Returns the texture of the resource, in the order of the images of the
+/// resource layer.
+fn resource_texture_index(resource: TileResource) -> TileTextureIndex {
+    TileTextureIndex(match resource {
+        TileResource::Bonus(BonusResource::Bananas) => 0,
+        TileResource::Bonus(BonusResource::Cattle) => 1,
+        TileResource::Bonus(BonusResource::Copper) => 2,
+        TileResource::Bonus(BonusResource::Deer) => 3,
+        TileResource::Bonus(BonusResource::Fish) => 4,
+        TileResource::Bonus(BonusResource::Rice) => 5,
+        TileResource::Bonus(BonusResource::Sheep) => 6,
+        TileResource::Bonus(BonusResource::Stone) => 7,
+        TileResource::Bonus(BonusResource::Wheat) => 8,
+        TileResource::Strategic(StrategicResource::Horses) => 9,
+        TileResource::Strategic(StrategicResource::Iron) => 10,
+        TileResource::Strategic(StrategicResource::Niter) => 11,
+        TileResource::Strategic(StrategicResource::Coal) => 12,
+        TileResource::Strategic(StrategicResource::Oil) => 13,
+        TileResource::Strategic(StrategicResource::Aluminum) => 14,
+        TileResource::Strategic(StrategicResource::Uranium) => 15,
+    })
+}
@@ -4,5 +4,7 @@ use bevy::prelude::*;
 pub enum GameState {
     #[default]
     GameSetup,
+    /// The map is being generated, while the loading screen is shown.
+    Loading,
     InGame,
 }
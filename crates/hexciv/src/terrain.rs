@@ -1,35 +1,42 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::error::Error;
+use std::f64::consts::TAU;
 use std::iter::zip;
-use std::ops::Add;
+use std::ops::{Add, Range, RangeInclusive};
+use std::task::Poll;
 
 use bevy::prelude::*;
+use bevy::tasks::{
+    AsyncComputeTaskPool, ComputeTaskPool, ParallelSlice as _, Task, block_on, poll_once,
+};
 use bevy_ecs_tilemap::helpers::hex_grid::axial::AxialPos;
 use bevy_ecs_tilemap::helpers::hex_grid::neighbors::{HEX_DIRECTIONS, HexNeighbors};
+use bevy_ecs_tilemap::helpers::hex_grid::offset::RowOddPos;
 use bevy_ecs_tilemap::prelude::*;
 use bevy_pancam::{DirectionKeys, PanCam};
 use bitvec::prelude::*;
 use derive_more::Display;
-use fastlem_random_terrain::{Site2D, Terrain2D, generate_terrain};
+use fastlem_random_terrain::{Site2D, Stage, Terrain2D, TerrainGenError, TerrainGeneration};
 use fastrand_contrib::RngExt as _;
 use itertools::{Itertools as _, chain, repeat_n};
-use num_enum::{IntoPrimitive, TryFromPrimitive};
+use noise::{NoiseFn as _, Perlin};
 use ordered_float::NotNan;
+use serde::{Deserialize, Serialize};
 use strum::VariantArray;
 
-use crate::game_setup::MapRng;
+use crate::game_setup::{MapRng, MapScript, MapSize, MapWrapping, NumPlayers};
 use crate::layer::{
-    BaseTerrainLayer, BaseTerrainLayerFilter, CivilianUnitLayer, LandMilitaryUnitLayer,
-    LayerZIndex as _, RiverLayer, RiverLayerFilter, TerrainFeaturesLayer,
-    TerrainFeaturesLayerFilter, UnitSelectionLayer, UnitStateLayer,
+    BaseTerrainLayer, BaseTerrainLayerFilter, CivilianUnitLayer, CliffLayer, CliffLayerFilter,
+    FogOfWarLayer, LandMilitaryUnitLayer, LayerZIndex as _, MovementPathLayer, ResourceLayer,
+    RiverLayer, RiverLayerFilter, TerrainFeaturesLayer, TerrainFeaturesLayerFilter,
+    UnitSelectionLayer, UnitStateLayer,
 };
+use crate::map::{GameMap, Tile};
+use crate::resource::{MapStrategicResources, generate_strategic_resources};
+use crate::state::GameState;
 use crate::unit::ActionsLegend;
 
-// IMPORTANT: The map's dimensions must both be even numbers, due to the
-// assumptions being made in our calculations.
-const MAP_SIDE_LENGTH_X: u32 = 74;
-const MAP_SIDE_LENGTH_Y: u32 = 46;
-
 // IMPORTANT: The tile's dimensions must follow the aspect ratio of a regular
 // hexagon.
 const TILE_SIZE: TilemapTileSize = TilemapTileSize { x: 100.0, y: 115.0 };
@@ -47,51 +54,21 @@ const CENTER_TO_CENTER_X: f64 = GRID_SIZE.x as f64;
 /// The center-to-center distance between adjacent rows of tiles.
 const CENTER_TO_CENTER_Y: f64 = 0.75 * GRID_SIZE.y as f64;
 
-const BOUND_WIDTH: f64 =
-    ((MAP_SIDE_LENGTH_X - 1) as f64 * CENTER_TO_CENTER_X + GRID_SIZE.x as f64 + ODD_ROW_OFFSET)
-        / 100.0;
-const BOUND_HEIGHT: f64 =
-    ((MAP_SIDE_LENGTH_Y - 1) as f64 * CENTER_TO_CENTER_Y + GRID_SIZE.y as f64) / 100.0;
-
-const BOUND_MIN: Site2D = Site2D {
-    x: -BOUND_WIDTH / 2.0,
-    y: -BOUND_HEIGHT / 2.0,
-};
-const BOUND_MAX: Site2D = Site2D {
-    x: BOUND_WIDTH / 2.0,
-    y: BOUND_HEIGHT / 2.0,
-};
-const BOUND_RANGE: Site2D = Site2D {
-    x: BOUND_WIDTH,
-    y: BOUND_HEIGHT,
-};
-
 /// Offsets of vertices that lie in each [`HexVertexDirection`].
 ///
-/// The offsets are in [`Site2D`] coordinates.
-const VERTEX_OFFSETS: [(f32, f32); 6] = [
-    (GRID_SIZE.x * 0.5, -GRID_SIZE.y * 0.25),
-    (0.0, -GRID_SIZE.y * 0.5),
-    (-GRID_SIZE.x * 0.5, -GRID_SIZE.y * 0.25),
-    (-GRID_SIZE.x * 0.5, GRID_SIZE.y * 0.25),
-    (0.0, GRID_SIZE.y * 0.5),
-    (GRID_SIZE.x * 0.5, GRID_SIZE.y * 0.25),
-];
+/// The offsets are in [`Vertex`] coordinates.
+const VERTEX_OFFSETS: [(i32, i32); 6] = [(1, -1), (0, -2), (-1, -1), (-1, 1), (0, 2), (1, 1)];
 
 /// Offsets to the closest vertex of tiles that lie in each
 /// [`HexVertexDirection`].
 ///
-/// The offsets are in [`Site2D`] coordinates.
-const EXTENDED_VERTEX_OFFSETS: [(f32, f32); 6] = [
-    (GRID_SIZE.x, -GRID_SIZE.y * 0.5),
-    (0.0, -GRID_SIZE.y),
-    (-GRID_SIZE.x, -GRID_SIZE.y * 0.5),
-    (-GRID_SIZE.x, GRID_SIZE.y * 0.5),
-    (0.0, GRID_SIZE.y),
-    (GRID_SIZE.x, GRID_SIZE.y * 0.5),
-];
+/// The offsets are in [`Vertex`] coordinates.
+const EXTENDED_VERTEX_OFFSETS: [(i32, i32); 6] =
+    [(2, -2), (0, -4), (-2, -2), (-2, 2), (0, 4), (2, 2)];
 
 const FRIGID_ZONE_TERRAIN_CHOICES: [BaseTerrain; 2] = [BaseTerrain::Tundra, BaseTerrain::Snow];
+// The base terrain choices for the other climate zones are ordered from the
+// driest to the wettest. See [`choose_base_terrain`].
 const TEMPERATE_ZONE_TERRAIN_CHOICES: [BaseTerrain; 4] = [
     BaseTerrain::Plains,
     BaseTerrain::Plains,
@@ -99,22 +76,22 @@ const TEMPERATE_ZONE_TERRAIN_CHOICES: [BaseTerrain; 4] = [
     BaseTerrain::Grassland,
 ];
 const SUBTROPICS_TERRAIN_CHOICES: [BaseTerrain; 7] = [
+    BaseTerrain::Desert,
+    BaseTerrain::Desert,
     BaseTerrain::Plains,
     BaseTerrain::Plains,
     BaseTerrain::Plains,
     BaseTerrain::Grassland,
     BaseTerrain::Grassland,
-    BaseTerrain::Desert,
-    BaseTerrain::Desert,
 ];
 const TROPICS_TERRAIN_CHOICES: [BaseTerrain; 7] = [
+    BaseTerrain::Desert,
     BaseTerrain::Plains,
     BaseTerrain::Plains,
     BaseTerrain::Grassland,
     BaseTerrain::Grassland,
     BaseTerrain::Grassland,
     BaseTerrain::Grassland,
-    BaseTerrain::Desert,
 ];
 
 const WOODS_CHOICES: [bool; 5] = [true, false, false, false, false];
@@ -125,41 +102,238 @@ const SUBTROPICAL_RAINFOREST_CHOICES: [bool; 10] = [
 const TROPICAL_RAINFOREST_CHOICES: [bool; 3] = [true, false, false];
 const OASIS_CHOICES: [bool; 5] = [true, false, false, false, false];
 const ICE_CHOICES: [bool; 4] = [true, true, true, false];
+const MARSH_CHOICES: [bool; 3] = [true, false, false];
+const MINOR_RIVER_FLOODPLAINS_CHOICES: [bool; 3] = [true, false, false];
+const MAJOR_RIVER_FLOODPLAINS_CHOICES: [bool; 3] = [true, true, false];
+
+/// The number of continents on a map with the continents map script.
+const CONTINENTS_NUM_CONTINENTS: RangeInclusive<usize> = 2..=3;
+/// The number of tiles on the map for each island.
+const ISLANDS_TILES_PER_ISLAND: usize = 280;
+
+/// The names given to rivers, in no particular order.
+const RIVER_NAMES: [&str; 24] = [
+    "Amazon",
+    "Amur",
+    "Danube",
+    "Dnieper",
+    "Euphrates",
+    "Ganges",
+    "Indus",
+    "Irrawaddy",
+    "Jordan",
+    "Loire",
+    "Mekong",
+    "Mississippi",
+    "Murray",
+    "Niger",
+    "Nile",
+    "Orinoco",
+    "Rhine",
+    "Rhone",
+    "Tagus",
+    "Thames",
+    "Tigris",
+    "Volga",
+    "Yangtze",
+    "Zambezi",
+];
+
+/// The minimum number of neighboring tiles of a starting position which
+/// starting units can be spawned on.
+const STARTING_POSITION_MIN_ALLOWABLE_NEIGHBORS: usize = 3;
+
+/// The maximum number of tiles in an enclosed body of water for it to be
+/// considered a lake.
+const LAKE_MAX_SIZE: usize = 9;
+
+/// The elevation below which tiles are submerged.
+const SEA_LEVEL: f64 = 0.05;
+
+/// The fraction of the land tiles, from the lowest, which are low-lying enough
+/// for marsh.
+const MARSH_LOWLAND_RATIO: f64 = 0.2;
+/// The minimum moisture of marsh.
+const MARSH_MIN_MOISTURE: f64 = 0.5;
+
+/// The scale of the noise in the moisture, in [`Site2D`] coordinates.
+const MOISTURE_NOISE_SCALE: f64 = 0.1;
+const MOISTURE_NOISE_WEIGHT: f64 = 0.65;
+/// The distance (in tiles) inland over which the moisture carried from the
+/// ocean decays by a factor of e.
+const MOISTURE_OCEAN_DECAY_DISTANCE: f64 = 10.0;
+const MOISTURE_OCEAN_WEIGHT: f64 = 0.35;
+/// The maximum distance (in tiles) downwind of mountains which lies in their
+/// rain shadow.
+const RAIN_SHADOW_DISTANCE: u32 = 6;
+const RAIN_SHADOW_WEIGHT: f64 = 0.3;
+
+/// The width of the continental shelf, by the relief of the adjacent land.
+///
+/// Each entry is the (exclusive) upper bound of the relief, and the number of
+/// tiles of coast extending out from the shore.
+const CONTINENTAL_SHELF_WIDTHS: [(f64, u32); 3] = [(1.0, 3), (5.0, 2), (f64::INFINITY, 1)];
+
+/// The number of times the terrain generation is attempted with different seeds
+/// before giving up.
+const MAX_TERRAIN_GENERATION_ATTEMPTS: u32 = 3;
+
+/// The width of the band along the west edge of the map over which the terrain
+/// is blended into the terrain beyond the east edge, as a fraction of the width
+/// of the map, where the map wraps around east-west.
+const WRAP_BLEND_RATIO: f64 = 0.2;
 
 #[derive(Resource)]
-pub struct MapTerrain(Terrain2D);
+pub struct MapTerrain {
+    terrain: Terrain2D,
+    /// The range of x in [`Site2D`] coordinates which is repeated along the
+    /// x-axis, if the map wraps around east-west.
+    wrap_range: Option<Range<f64>>,
+    /// The elevation at each vertex of the tiles, and at the closest vertex of
+    /// the tiles beyond them, or `None` outside of the terrain.
+    vertex_elevations: HashMap<Vertex, Option<NotNan<f64>>>,
+}
+
+/// A vertex of the tiles, which is shared by up to three tiles.
+///
+/// The coordinates are in units of half the width of a tile along the x-axis,
+/// and a quarter of the height of a tile along the y-axis, from the minimum
+/// corner of the map in [`Site2D`] coordinates.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct Vertex {
+    x: i32,
+    y: i32,
+}
+
+/// The terrain generation in progress, while the loading screen is shown.
+#[derive(Resource)]
+pub struct MapTerrainGeneration {
+    config: fastlem_random_terrain::Config,
+    bound_min: Site2D,
+    bound_max: Site2D,
+    wrap_range: Option<Range<f64>>,
+    /// The number of times the terrain generation has been started.
+    attempts: u32,
+    stage: Option<Stage>,
+    progress: f64,
+    /// The terrain generation, unless its next step is being run in `task`.
+    generation: Option<TerrainGeneration>,
+    task: Option<Task<(TerrainGeneration, TerrainGenerationPoll)>>,
+    /// The error which the terrain generation has failed with, after giving up.
+    error: Option<TerrainGenError>,
+}
+
+type TerrainGenerationPoll = Poll<Result<Terrain2D, TerrainGenError>>;
+
+/// The targets for classifying the land tiles into flat land, hills and
+/// mountains by their elevation.
+///
+/// The targets are percentiles of the elevation of the land tiles, rather than
+/// fixed elevations, so that the coverage of hills and mountains is consistent
+/// regardless of the seed and the land ratio.
+///
+/// It is part of the game setup, so that every peer classifies the tiles in the
+/// same way.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize, Resource)]
+#[serde(try_from = "ElevationClassifierConfigFields")]
+pub struct ElevationClassifierConfig {
+    /// The fraction of the land tiles which are hills (0.0-1.0).
+    hills_ratio: NotNan<f64>,
+    /// The fraction of the land tiles which are mountains (0.0-1.0).
+    ///
+    /// Together with `hills_ratio`, it must not be greater than 1.0.
+    mountains_ratio: NotNan<f64>,
+}
+
+/// The fields of [`ElevationClassifierConfig`] before they are validated.
+#[derive(Deserialize)]
+struct ElevationClassifierConfigFields {
+    hills_ratio: f64,
+    mountains_ratio: f64,
+}
+
+#[derive(Debug, Display)]
+pub enum ElevationClassifierConfigError {
+    #[display("invalid config: {field} should be {expected}")]
+    InvalidConfig {
+        field: &'static str,
+        expected: &'static str,
+    },
+}
 
-#[derive(Copy, Clone, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
-#[repr(u32)]
+/// The minimum elevations of hills and mountains on a map, as classified by
+/// [`ElevationClassifierConfig`], and the maximum elevation of marsh.
+#[derive(Copy, Clone, Debug, Resource)]
+pub struct ElevationThresholds {
+    hills_min_elevation: NotNan<f64>,
+    mountains_min_elevation: NotNan<f64>,
+    marsh_max_elevation: NotNan<f64>,
+}
+
+/// The starting position of each player, in order of [`PlayerIndex`].
+///
+/// [`PlayerIndex`]: crate::player::PlayerIndex
+#[derive(Resource)]
+pub struct StartingPositions(pub Vec<TilePos>);
+
+/// A camera which renders the map once more beyond the east / west edge, where
+/// the map wraps around east-west.
+#[derive(Component)]
+pub struct WrapAroundCamera {
+    /// The number of map widths by which the camera is offset from the main
+    /// camera.
+    offset: f32,
+}
+
+/// The river entities flowing along the edges of each tile.
+#[derive(Resource)]
+pub struct MapRivers(HashMap<TilePos, Vec<Entity>>);
+
+/// The moisture of each land tile, from 0 (arid) to 1 (humid).
+#[derive(Resource)]
+pub struct MapMoisture(HashMap<TilePos, NotNan<f64>>);
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize)]
 pub enum BaseTerrain {
-    Plains = 0,
-    Grassland = 1,
-    Desert = 2,
-    Tundra = 3,
-    Snow = 4,
-    PlainsHills = 5,
-    GrasslandHills = 6,
-    DesertHills = 7,
-    TundraHills = 8,
-    SnowHills = 9,
-    PlainsMountains = 10,
-    GrasslandMountains = 11,
-    DesertMountains = 12,
-    TundraMountains = 13,
-    SnowMountains = 14,
-    Coast = 15,
-    Ocean = 16,
+    Plains,
+    Grassland,
+    Desert,
+    Tundra,
+    Snow,
+    PlainsHills,
+    GrasslandHills,
+    DesertHills,
+    TundraHills,
+    SnowHills,
+    PlainsMountains,
+    GrasslandMountains,
+    DesertMountains,
+    TundraMountains,
+    SnowMountains,
+    Coast,
+    Ocean,
+    Lake,
 }
 
-#[derive(Copy, Clone, IntoPrimitive)]
-#[repr(u32)]
+#[derive(Copy, Clone)]
 pub enum BaseTerrainVariant {
-    Hills = 5,
-    Mountains = 10,
+    Hills,
+    Mountains,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Component)]
-pub struct River;
+/// A river network, made up of all the river edges which flow into the same
+/// mouth.
+///
+/// Each river is spawned along with its [`Name`], and can be looked up from the
+/// tiles it flows along through [`MapRivers`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Component)]
+pub struct River {
+    pub edges: Vec<RiverEdge>,
+    /// The river edge where the river ends, i.e. flows into the sea / a lake or
+    /// off the map.
+    pub mouth: RiverEdge,
+    pub max_stream_order: StreamOrder,
+}
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Component)]
 pub struct RiverEdge {
@@ -194,21 +368,30 @@ pub struct StreamOrder(pub u8);
 
 pub type RiverHexEdges = BitArr!(for 6, in u32, Lsb0);
 
-#[derive(Copy, Clone, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
-#[repr(u32)]
+pub type CliffHexEdges = BitArr!(for 6, in u32, Lsb0);
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize)]
 pub enum TerrainFeatures {
-    Woods = 0,
-    Rainforest = 1,
-    Marsh = 2,
-    Floodplains = 3,
-    Oasis = 4,
-    Cliffs = 5,
-    Ice = 6,
+    Woods,
+    Rainforest,
+    Marsh,
+    Floodplains,
+    Oasis,
+    Cliffs,
+    Ice,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, SystemSet)]
 pub struct SpawnTilemapSet;
 
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+enum ClimateZone {
+    Frigid,
+    Temperate,
+    Subtropics,
+    Tropics,
+}
+
 enum EarthLatitude {
     ArticCirle,
     TropicOfCancer,
@@ -232,6 +415,8 @@ impl BaseTerrain {
         Self::SnowMountains,
     ];
 
+    pub const WATER: [Self; 3] = [Self::Coast, Self::Ocean, Self::Lake];
+
     pub fn is_hills(&self) -> bool {
         Self::HILLS.contains(self)
     }
@@ -239,32 +424,44 @@ impl BaseTerrain {
     pub fn is_mountains(&self) -> bool {
         Self::MOUNTAINS.contains(self)
     }
+
+    pub fn is_water(&self) -> bool {
+        Self::WATER.contains(self)
+    }
 }
 
 impl Add<BaseTerrainVariant> for BaseTerrain {
     type Output = Self;
 
     fn add(self, rhs: BaseTerrainVariant) -> Self::Output {
-        match self {
-            Self::Plains | Self::Grassland | Self::Desert | Self::Tundra | Self::Snow => {
-                let base: u32 = self.into();
-                let variant: u32 = rhs.into();
-                Self::try_from(base + variant).unwrap()
-            },
-            Self::PlainsHills
-            | Self::GrasslandHills
-            | Self::DesertHills
-            | Self::TundraHills
-            | Self::SnowHills
-            | Self::PlainsMountains
-            | Self::GrasslandMountains
-            | Self::DesertMountains
-            | Self::TundraMountains
-            | Self::SnowMountains => {
+        match (self, rhs) {
+            (Self::Plains, BaseTerrainVariant::Hills) => Self::PlainsHills,
+            (Self::Grassland, BaseTerrainVariant::Hills) => Self::GrasslandHills,
+            (Self::Desert, BaseTerrainVariant::Hills) => Self::DesertHills,
+            (Self::Tundra, BaseTerrainVariant::Hills) => Self::TundraHills,
+            (Self::Snow, BaseTerrainVariant::Hills) => Self::SnowHills,
+            (Self::Plains, BaseTerrainVariant::Mountains) => Self::PlainsMountains,
+            (Self::Grassland, BaseTerrainVariant::Mountains) => Self::GrasslandMountains,
+            (Self::Desert, BaseTerrainVariant::Mountains) => Self::DesertMountains,
+            (Self::Tundra, BaseTerrainVariant::Mountains) => Self::TundraMountains,
+            (Self::Snow, BaseTerrainVariant::Mountains) => Self::SnowMountains,
+            (
+                Self::PlainsHills
+                | Self::GrasslandHills
+                | Self::DesertHills
+                | Self::TundraHills
+                | Self::SnowHills
+                | Self::PlainsMountains
+                | Self::GrasslandMountains
+                | Self::DesertMountains
+                | Self::TundraMountains
+                | Self::SnowMountains,
+                _,
+            ) => {
                 unimplemented!("base terrain variants are not stackable");
             },
-            Self::Coast | Self::Ocean => {
-                unimplemented!("coast and ocean base terrain do not have variants");
+            (Self::Coast | Self::Ocean | Self::Lake, _) => {
+                unimplemented!("coast, ocean and lake base terrain do not have variants");
             },
         }
     }
@@ -272,6 +469,96 @@ impl Add<BaseTerrainVariant> for BaseTerrain {
 
 impl River {
     pub const MIN_LEN: usize = 2;
+
+    pub fn new(edges: Vec<RiverEdge>, map_size: &TilemapSize, map_wrapping: MapWrapping) -> Self {
+        let mouth = *edges
+            .iter()
+            .max_by_key(|river_edge| {
+                let is_end = !edges.iter().any(|other_river_edge| {
+                    other_river_edge.upstream_vertex(map_size, map_wrapping)
+                        == river_edge.downstream_vertex(map_size, map_wrapping)
+                });
+                (is_end, river_edge.stream_order)
+            })
+            .expect("river should have at least one river edge");
+        let max_stream_order = edges
+            .iter()
+            .map(|river_edge| river_edge.stream_order)
+            .max()
+            .expect("river should have at least one river edge");
+        Self {
+            edges,
+            mouth,
+            max_stream_order,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+}
+
+impl RiverEdge {
+    /// Returns the vertex where the river edge begins, as the positions of the
+    /// three tiles around it.
+    pub fn upstream_vertex(
+        &self,
+        map_size: &TilemapSize,
+        map_wrapping: MapWrapping,
+    ) -> [AxialPos; 3] {
+        let vertex_direction: HexVertexDirection = (self.source, self.destination)
+            .try_into()
+            .expect("`(source, destination)` should match a valid offset");
+        hex_vertex(self.source, vertex_direction, map_size, map_wrapping)
+    }
+
+    /// Returns the vertex where the river edge ends, as the positions of the
+    /// three tiles around it.
+    pub fn downstream_vertex(
+        &self,
+        map_size: &TilemapSize,
+        map_wrapping: MapWrapping,
+    ) -> [AxialPos; 3] {
+        let vertex_direction: HexVertexDirection = (self.destination, self.source)
+            .try_into()
+            .expect("`(destination, source)` should match a valid offset");
+        hex_vertex(self.destination, vertex_direction, map_size, map_wrapping)
+    }
+}
+
+impl MapRivers {
+    /// Returns the river entities flowing along the edges of the tile.
+    pub fn get(&self, tile_pos: &TilePos) -> &[Entity] {
+        self.0.get(tile_pos).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl MapMoisture {
+    pub fn get(&self, tile_pos: &TilePos) -> Option<NotNan<f64>> {
+        self.0.get(tile_pos).copied()
+    }
+}
+
+impl ClimateZone {
+    fn from_latitude(latitude: NotNan<f64>) -> Self {
+        if *latitude >= EarthLatitude::ArticCirle.latitude()
+            || *latitude <= EarthLatitude::AntarcticCircle.latitude()
+        {
+            Self::Frigid
+        } else if *latitude >= 35.0 || *latitude <= -35.0 {
+            Self::Temperate
+        } else if *latitude >= EarthLatitude::TropicOfCancer.latitude()
+            || *latitude <= EarthLatitude::TropicOfCapricorn.latitude()
+        {
+            Self::Subtropics
+        } else {
+            Self::Tropics
+        }
+    }
 }
 
 impl EarthLatitude {
@@ -311,6 +598,8 @@ impl HexVertexDirection {
 
 impl Error for HexVertexDirectionError {}
 
+impl Error for ElevationClassifierConfigError {}
+
 impl Add for StreamOrder {
     type Output = Self;
 
@@ -323,35 +612,451 @@ impl Add for StreamOrder {
     }
 }
 
-/// Generates the initial tilemap.
-pub fn spawn_tilemap(
+impl Default for ElevationClassifierConfig {
+    fn default() -> Self {
+        Self::new(0.3, 0.08).expect("the default config should be valid")
+    }
+}
+
+impl TryFrom<ElevationClassifierConfigFields> for ElevationClassifierConfig {
+    type Error = ElevationClassifierConfigError;
+
+    fn try_from(fields: ElevationClassifierConfigFields) -> Result<Self, Self::Error> {
+        Self::new(fields.hills_ratio, fields.mountains_ratio)
+    }
+}
+
+impl ElevationClassifierConfig {
+    pub fn new(
+        hills_ratio: f64,
+        mountains_ratio: f64,
+    ) -> Result<Self, ElevationClassifierConfigError> {
+        let invalid = |field, expected| {
+            Err(ElevationClassifierConfigError::InvalidConfig { field, expected })
+        };
+
+        if !(0.0..=1.0).contains(&hills_ratio) {
+            return invalid("hills_ratio", "within 0.0-1.0");
+        }
+        if !(0.0..=1.0).contains(&mountains_ratio) {
+            return invalid("mountains_ratio", "within 0.0-1.0");
+        }
+        if hills_ratio + mountains_ratio > 1.0 {
+            return invalid("mountains_ratio", "at most 1.0 minus hills_ratio");
+        }
+
+        Ok(Self {
+            hills_ratio: NotNan::new(hills_ratio).expect("hills_ratio should not be NaN"),
+            mountains_ratio: NotNan::new(mountains_ratio)
+                .expect("mountains_ratio should not be NaN"),
+        })
+    }
+
+    pub fn hills_ratio(&self) -> f64 {
+        *self.hills_ratio
+    }
+
+    pub fn mountains_ratio(&self) -> f64 {
+        *self.mountains_ratio
+    }
+
+    /// Returns the minimum elevations of hills and mountains, such that the
+    /// targets are met among the land tiles, and the maximum elevation of
+    /// marsh, such that only the lowest land tiles are low-lying enough.
+    fn thresholds(&self, elevations: &HashMap<TilePos, NotNan<f64>>) -> ElevationThresholds {
+        let land_elevations: Vec<_> = elevations
+            .values()
+            .filter(|elevation| ***elevation >= SEA_LEVEL)
+            .sorted()
+            .collect();
+        // Returns the elevation above which lies the given fraction of the land tiles.
+        let percentile = |ratio: f64| {
+            let len = land_elevations.len() as f64;
+            let i = ((1.0 - ratio) * len).floor() as usize;
+            land_elevations
+                .get(i)
+                .map_or(NotNan::new(f64::INFINITY).unwrap(), |&&elevation| elevation)
+        };
+
+        ElevationThresholds {
+            hills_min_elevation: percentile(self.hills_ratio() + self.mountains_ratio()),
+            mountains_min_elevation: percentile(self.mountains_ratio()),
+            marsh_max_elevation: percentile(1.0 - MARSH_LOWLAND_RATIO),
+        }
+    }
+}
+
+impl MapTerrainGeneration {
+    /// Returns the stage of the terrain generation which is being run, or
+    /// `None` if the terrain has been generated.
+    pub fn stage(&self) -> Option<Stage> {
+        self.stage
+    }
+
+    /// Returns the fraction of the work of the terrain generation which has
+    /// been done (0.0-1.0).
+    pub fn progress(&self) -> f64 {
+        self.progress
+    }
+
+    /// Returns the error which the terrain generation has failed with, if it
+    /// has been given up on.
+    pub fn error(&self) -> Option<&TerrainGenError> {
+        self.error.as_ref()
+    }
+
+    /// Starts the terrain generation from the first stage.
+    fn start(&mut self) {
+        self.attempts += 1;
+        self.task = None;
+        match TerrainGeneration::new(
+            self.config.clone(),
+            self.bound_min,
+            self.bound_max,
+            Site2D {
+                x: self.bound_max.x - self.bound_min.x,
+                y: self.bound_max.y - self.bound_min.y,
+            },
+        ) {
+            Ok(generation) => {
+                self.stage = generation.stage();
+                self.progress = generation.progress();
+                self.generation = Some(generation);
+            },
+            Err(err) => {
+                self.fail(err);
+            },
+        }
+    }
+
+    /// Starts the terrain generation over with a new seed after it has failed,
+    /// unless the failure is not due to the seed, or it has already been
+    /// attempted too many times.
+    ///
+    /// The new seed is drawn from the map RNG, so that every peer retries with
+    /// the same seed.
+    fn retry(&mut self, rng: &mut fastrand::Rng, err: TerrainGenError) {
+        if matches!(
+            err,
+            TerrainGenError::InvalidConfig { .. } | TerrainGenError::InvalidBounds
+        ) || self.attempts >= MAX_TERRAIN_GENERATION_ATTEMPTS
+        {
+            self.fail(err);
+            return;
+        }
+
+        warn!(%err, "failed to generate terrain, retrying with a new seed");
+        self.config.seed = rng.u32(..);
+        self.start();
+    }
+
+    fn fail(&mut self, err: TerrainGenError) {
+        error!(%err, "failed to generate terrain");
+        self.generation = None;
+        self.error = Some(err);
+    }
+}
+
+impl MapTerrain {
+    /// Returns the elevation at the site.
+    ///
+    /// Where the map wraps around east-west, the terrain beyond the east edge of
+    /// the map is blended into the terrain along the west edge, so that there is
+    /// no seam.
+    pub fn get_elevation(&self, site: &Site2D) -> Option<f64> {
+        let Some(wrap_range) = &self.wrap_range else {
+            return self.terrain.get_elevation(site);
+        };
+        let wrap_width = wrap_range.end - wrap_range.start;
+        let x = wrap_range.start + (site.x - wrap_range.start).rem_euclid(wrap_width);
+        let elevation = self.terrain.get_elevation(&Site2D { x, y: site.y })?;
+        let t = (x - wrap_range.start) / (wrap_width * WRAP_BLEND_RATIO);
+        if t >= 1.0 {
+            return Some(elevation);
+        }
+        let wrapped_elevation = self.terrain.get_elevation(&Site2D {
+            x: x + wrap_width,
+            y: site.y,
+        })?;
+        Some(wrapped_elevation + (elevation - wrapped_elevation) * t)
+    }
+
+    /// Creates the map terrain from the generated terrain, sampling the
+    /// elevation at the vertices of the tiles.
+    fn new(terrain: Terrain2D, wrap_range: Option<Range<f64>>, map_size: &TilemapSize) -> Self {
+        let mut map_terrain = Self {
+            terrain,
+            wrap_range,
+            vertex_elevations: HashMap::new(),
+        };
+        map_terrain.vertex_elevations = map_terrain.sample_vertex_elevations(map_size);
+        map_terrain
+    }
+
+    /// Returns the elevation at the vertex, or `None` if it is outside of the
+    /// terrain.
+    fn vertex_elevation(&self, vertex: &Vertex) -> Option<NotNan<f64>> {
+        *self
+            .vertex_elevations
+            .get(vertex)
+            .expect("the elevation at the vertex should have been sampled")
+    }
+
+    /// Samples the elevation at each vertex of the tiles, and at the closest
+    /// vertex of the tiles beyond them.
+    ///
+    /// Each vertex is shared by up to three tiles, but is only sampled once. The
+    /// vertices are sampled in parallel, where the platform supports it.
+    fn sample_vertex_elevations(
+        &self,
+        map_size: &TilemapSize,
+    ) -> HashMap<Vertex, Option<NotNan<f64>>> {
+        let vertices: Vec<_> = (0..map_size.x)
+            .cartesian_product(0..map_size.y)
+            .flat_map(|(x, y)| {
+                let tile_pos = TilePos { x, y };
+                chain(VERTEX_OFFSETS, EXTENDED_VERTEX_OFFSETS)
+                    .map(move |vertex_offset| Vertex::of_tile(&tile_pos, map_size, vertex_offset))
+            })
+            .unique()
+            .collect();
+
+        vertices
+            .par_splat_map(ComputeTaskPool::get(), None, |_, vertices| {
+                vertices
+                    .iter()
+                    .map(|vertex| {
+                        let elevation = self
+                            .get_elevation(&vertex.site(map_size))
+                            .and_then(|elevation| NotNan::new(elevation).ok());
+                        (*vertex, elevation)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
+impl Vertex {
+    /// Returns the vertex at the given offset from the center of the tile.
+    fn of_tile(
+        tile_pos: &TilePos,
+        map_size: &TilemapSize,
+        (vertex_offset_x, vertex_offset_y): (i32, i32),
+    ) -> Self {
+        let x = 1 + 2 * tile_pos.x as i32 + if tile_pos.y % 2 == 0 { 0 } else { 1 };
+        let y = 2 + 3 * (map_size.y - tile_pos.y - 1) as i32;
+        Self {
+            x: x + vertex_offset_x,
+            y: y + vertex_offset_y,
+        }
+    }
+
+    /// Returns the [`Site2D`] of the vertex.
+    fn site(&self, map_size: &TilemapSize) -> Site2D {
+        let bound_range = bound_range(map_size);
+        Site2D {
+            x: -bound_range.x / 2.0 + f64::from(self.x) * (0.5 * CENTER_TO_CENTER_X) / 100.0,
+            y: -bound_range.y / 2.0 + f64::from(self.y) * (0.25 * f64::from(GRID_SIZE.y)) / 100.0,
+        }
+    }
+}
+
+/// Starts generating the terrain of the map.
+///
+/// The terrain is generated one step at a time by
+/// [`advance_terrain_generation`], while the loading screen is shown.
+pub fn start_terrain_generation(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
     mut map_rng: ResMut<MapRng>,
-    actions_legend_text_query: Single<(&mut Text,), With<ActionsLegend>>,
+    map_size: Res<MapSize>,
+    map_script: Res<MapScript>,
+    map_wrapping: Res<MapWrapping>,
 ) {
     let rng = &mut map_rng.0;
-    info!(seed = rng.get_seed(), "map seed");
+    info!(
+        seed = rng.get_seed(),
+        ?map_size,
+        ?map_script,
+        ?map_wrapping,
+        "map seed"
+    );
+
+    let map_size = map_size.tilemap_size();
+
+    let bound_range = bound_range(&map_size);
+    let seed = rng.u32(..);
+    let land_ratio = rng.f64_range(0.29..=0.6);
+    let (landmass_centers, landmass_radius) =
+        choose_landmasses(rng, *map_script, &map_size, bound_range);
+    let config = fastlem_random_terrain::Config {
+        seed,
+        land_ratio,
+        landmass_centers,
+        landmass_radius,
+        ..Default::default()
+    };
+    info!(?config, "fastlem-random-terrain config");
+    let bound_min = Site2D {
+        x: -bound_range.x / 2.0,
+        y: -bound_range.y / 2.0,
+    };
+    // Where the map wraps around east-west, the terrain is generated beyond the
+    // east edge of the map, to be blended into the terrain along the west edge.
+    let wrap_range = map_wrapping
+        .wraps_east_west()
+        .then(|| bound_min.x..bound_min.x + wrap_width(&map_size));
+    let bound_max = Site2D {
+        x: wrap_range
+            .as_ref()
+            .map_or(bound_range.x / 2.0, |wrap_range| {
+                wrap_range.end + (wrap_range.end - wrap_range.start) * WRAP_BLEND_RATIO
+            }),
+        y: bound_range.y / 2.0,
+    };
 
-    let (mut actions_legend_text,) = actions_legend_text_query.into_inner();
+    let mut map_terrain_generation = MapTerrainGeneration {
+        config,
+        bound_min,
+        bound_max,
+        wrap_range,
+        attempts: 0,
+        stage: None,
+        progress: 0.0,
+        generation: None,
+        task: None,
+        error: None,
+    };
+    map_terrain_generation.start();
+    commands.insert_resource(map_terrain_generation);
+}
 
-    actions_legend_text.0 = "".to_owned();
+/// Runs the next step of the terrain generation as an async task, once the
+/// previous step has finished.
+///
+/// Only a single step is run at a time, so that the loading screen keeps being
+/// updated, even where the tasks are run on the main thread, such as on wasm.
+/// There, the loading screen still freezes while the landscape evolution model
+/// is being run, as it cannot be split into smaller steps. Once the terrain has
+/// been generated, the game is started.
+pub fn advance_terrain_generation(
+    mut commands: Commands,
+    mut map_rng: ResMut<MapRng>,
+    map_size: Res<MapSize>,
+    mut map_terrain_generation: ResMut<MapTerrainGeneration>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    let map_terrain_generation = &mut *map_terrain_generation;
 
-    let map_size = TilemapSize {
-        x: MAP_SIDE_LENGTH_X,
-        y: MAP_SIDE_LENGTH_Y,
-    };
+    if map_terrain_generation.error.is_some() {
+        // The loading screen shows the error, until the loading is cancelled.
+        return;
+    }
 
-    let terrain = {
-        let config = fastlem_random_terrain::Config {
-            seed: rng.u32(..),
-            land_ratio: rng.f64_range(0.29..=0.6),
-            ..Default::default()
+    if let Some(task) = &mut map_terrain_generation.task {
+        let Some((generation, poll)) = block_on(poll_once(task)) else {
+            // Keep waiting for the step to finish.
+            return;
         };
-        info!(?config, "fastlem-random-terrain config");
-        generate_terrain(&config, BOUND_MIN, BOUND_MAX, BOUND_RANGE)
+        map_terrain_generation.task = None;
+        match poll {
+            Poll::Ready(Ok(terrain)) => {
+                commands.remove_resource::<MapTerrainGeneration>();
+                commands.insert_resource(MapTerrain::new(
+                    terrain,
+                    map_terrain_generation.wrap_range.take(),
+                    &map_size.tilemap_size(),
+                ));
+                next_game_state.set(GameState::InGame);
+                return;
+            },
+            Poll::Ready(Err(err)) => {
+                map_terrain_generation.retry(&mut map_rng.0, err);
+                return;
+            },
+            Poll::Pending => {
+                map_terrain_generation.generation = Some(generation);
+            },
+        }
+    }
+
+    let mut generation = map_terrain_generation
+        .generation
+        .take()
+        .expect("the terrain generation should not be in a task");
+    map_terrain_generation.stage = generation.stage();
+    map_terrain_generation.progress = generation.progress();
+    map_terrain_generation.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+        let poll = generation.step();
+        (generation, poll)
+    }));
+}
+
+/// Runs the remaining steps of the terrain generation to completion, blocking
+/// until the terrain has been generated.
+///
+/// Returns the error which the terrain generation has failed with, if it has
+/// been given up on.
+pub fn finish_terrain_generation(
+    mut commands: Commands,
+    mut map_rng: ResMut<MapRng>,
+    map_size: Res<MapSize>,
+    mut map_terrain_generation: ResMut<MapTerrainGeneration>,
+) -> Result<(), TerrainGenError> {
+    let map_terrain_generation = &mut *map_terrain_generation;
+
+    let terrain = loop {
+        if let Some(err) = map_terrain_generation.error.take() {
+            return Err(err);
+        }
+        let generation = map_terrain_generation
+            .generation
+            .as_mut()
+            .expect("the terrain generation should not be in a task");
+        match generation.step() {
+            Poll::Ready(Ok(terrain)) => break terrain,
+            Poll::Ready(Err(err)) => {
+                map_terrain_generation.retry(&mut map_rng.0, err);
+            },
+            Poll::Pending => {},
+        }
     };
 
+    commands.remove_resource::<MapTerrainGeneration>();
+    commands.insert_resource(MapTerrain::new(
+        terrain,
+        map_terrain_generation.wrap_range.take(),
+        &map_size.tilemap_size(),
+    ));
+
+    Ok(())
+}
+
+/// Generates the initial tilemap.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_tilemap(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut map_rng: ResMut<MapRng>,
+    map_size: Res<MapSize>,
+    map_wrapping: Res<MapWrapping>,
+    map_terrain: Res<MapTerrain>,
+    elevation_classifier_config: Res<ElevationClassifierConfig>,
+    actions_legend_text_query: Option<Single<(&mut Text,), With<ActionsLegend>>>,
+) {
+    let rng = &mut map_rng.0;
+
+    // The actions legend is absent when generating the map headlessly.
+    if let Some(actions_legend_text_query) = actions_legend_text_query {
+        let (mut actions_legend_text,) = actions_legend_text_query.into_inner();
+
+        actions_legend_text.0 = "".to_owned();
+    }
+
+    let map_size = map_size.tilemap_size();
+
     // Spawn base terrain layer.
 
     let base_terrain_image_handles = vec![
@@ -372,77 +1077,79 @@ pub fn spawn_tilemap(
         asset_server.load("tiles/snow-mountains.png"),
         asset_server.load("tiles/coast.png"),
         asset_server.load("tiles/ocean.png"),
+        asset_server.load("tiles/lake.png"),
     ];
     let base_terrain_texture_vec = TilemapTexture::Vector(base_terrain_image_handles);
 
-    let mut base_terrain_tile_storage = TileStorage::empty(map_size);
+    let base_terrain_tile_storage = TileStorage::empty(map_size);
     let base_terrain_tilemap_entity = commands.spawn_empty().id();
 
+    let elevations: HashMap<TilePos, NotNan<f64>> = (0..map_size.x)
+        .cartesian_product(0..map_size.y)
+        .map(|(x, y)| {
+            let tile_pos = TilePos { x, y };
+            (tile_pos, tile_elevation(&map_terrain, &tile_pos, &map_size))
+        })
+        .collect();
+    let elevation_thresholds = elevation_classifier_config.thresholds(&elevations);
+    commands.insert_resource(elevation_thresholds);
+    let moisture = generate_moisture(
+        rng,
+        &map_size,
+        *map_wrapping,
+        &elevations,
+        elevation_thresholds.mountains_min_elevation,
+    );
+
+    // Rank the land tiles in each climate zone from the driest to the wettest,
+    // so that the proportions of the base terrain choices are kept.
+    let moisture_ranks: HashMap<TilePos, f64> = moisture
+        .iter()
+        .into_group_map_by(|(tile_pos, _)| {
+            ClimateZone::from_latitude(tile_latitude(tile_pos, &map_size))
+        })
+        .into_values()
+        .flat_map(|mut zone_moisture| {
+            zone_moisture.sort_by_key(|&(tile_pos, &moisture)| (moisture, *tile_pos));
+            let len = zone_moisture.len() as f64;
+            zone_moisture
+                .into_iter()
+                .enumerate()
+                .map(move |(i, (&tile_pos, _))| (tile_pos, i as f64 / len))
+        })
+        .collect();
+
+    let mut base_terrains: HashMap<TilePos, BaseTerrain> = HashMap::new();
     for x in 0..map_size.x {
         for y in 0..map_size.y {
             let tile_pos = TilePos { x, y };
-            let elevations = VERTEX_OFFSETS.map(|(vertex_offset_x, vertex_offset_y)| {
-                let x = BOUND_MIN.x
-                    + (f64::from(GRID_SIZE.x) / 2.0
-                        + f64::from(tile_pos.x) * CENTER_TO_CENTER_X
-                        + if tile_pos.y % 2 == 0 {
-                            0.0
-                        } else {
-                            ODD_ROW_OFFSET
-                        }
-                        + f64::from(vertex_offset_x))
-                        / 100.0;
-                let y = BOUND_MIN.y
-                    + (f64::from(GRID_SIZE.y) / 2.0
-                        + f64::from(map_size.y - tile_pos.y - 1) * CENTER_TO_CENTER_Y
-                        + f64::from(vertex_offset_y))
-                        / 100.0;
-                let site = Site2D { x, y };
-                terrain.get_elevation(&site)
-            });
-            let elevations: Vec<_> = elevations
-                .into_iter()
-                .flat_map(|elevation| {
-                    elevation
-                        .filter(|elevation| !elevation.is_nan())
-                        .map(|elevation| NotNan::new(elevation).unwrap())
-                })
-                .collect();
-            let elevation = elevations.iter().sum::<NotNan<_>>()
-                / NotNan::from(
-                    u8::try_from(elevations.len()).expect("`elevations.len()` should fit in `u8`"),
-                );
-            let texture_index = if elevation < NotNan::new(0.05).unwrap() {
-                TileTextureIndex(BaseTerrain::Ocean.into())
+            let elevation = elevations[&tile_pos];
+            let base_terrain = if *elevation < SEA_LEVEL {
+                BaseTerrain::Ocean
             } else {
-                let latitude = NotNan::new(-90.0).unwrap()
-                    + NotNan::new(180.0).unwrap()
-                        * ((NotNan::from(tile_pos.y) + NotNan::new(0.5).unwrap())
-                            / NotNan::from(map_size.y));
+                let climate_zone = ClimateZone::from_latitude(tile_latitude(&tile_pos, &map_size));
 
-                let base_terrain = choose_base_terrain_by_latitude(rng, latitude);
+                let base_terrain =
+                    choose_base_terrain(rng, climate_zone, moisture_ranks[&tile_pos]);
 
-                TileTextureIndex(if elevation >= NotNan::new(25.0).unwrap() {
-                    (base_terrain + BaseTerrainVariant::Mountains).into()
-                } else if elevation >= NotNan::new(5.0).unwrap() {
-                    (base_terrain + BaseTerrainVariant::Hills).into()
+                if elevation >= elevation_thresholds.mountains_min_elevation {
+                    base_terrain + BaseTerrainVariant::Mountains
+                } else if elevation >= elevation_thresholds.hills_min_elevation {
+                    base_terrain + BaseTerrainVariant::Hills
                 } else {
-                    base_terrain.into()
-                })
+                    base_terrain
+                }
             };
-            let tile_entity = commands
-                .spawn(TileBundle {
-                    position: tile_pos,
-                    tilemap_id: TilemapId(base_terrain_tilemap_entity),
-                    texture_index,
-                    ..Default::default()
-                })
-                .insert(BaseTerrainLayer)
-                .id();
-            base_terrain_tile_storage.set(&tile_pos, tile_entity);
+            base_terrains.insert(tile_pos, base_terrain);
         }
     }
 
+    let game_map = GameMap::from_fn(map_size, |tile_pos| {
+        Tile::new(base_terrains[tile_pos], elevations[tile_pos])
+    });
+
+    // The base terrain tiles are spawned from the game map, once the terrain is
+    // final.
     commands
         .entity(base_terrain_tilemap_entity)
         .insert(TilemapBundle {
@@ -508,17 +1215,65 @@ pub fn spawn_tilemap(
         })
         .insert(RiverLayer);
 
+    // Spawn cliff layer.
+
+    let cliff_image_handles = {
+        let image_map: BTreeMap<u32, Handle<Image>> = repeat_n([true, false].into_iter(), 6)
+            .multi_cartesian_product()
+            .map(|data| {
+                let mut bits: CliffHexEdges = BitArray::<_>::ZERO;
+                for (i, &v) in data.iter().enumerate() {
+                    bits.set(i, v);
+                }
+                (
+                    bits.load(),
+                    asset_server.load(format!(
+                        "tiles/cliff/cliff-{edges}.png",
+                        edges = data
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &v)| if v { i.to_string() } else { "x".to_owned() })
+                            .join("")
+                    )),
+                )
+            })
+            .collect();
+        let size = usize::try_from(*image_map.last_key_value().unwrap().0).unwrap() + 1;
+        let mut image_vec = vec![asset_server.load("tiles/transparent.png"); size];
+        for (key, image) in image_map {
+            image_vec[usize::try_from(key).unwrap()] = image;
+        }
+        image_vec
+    };
+    let cliff_texture_vec = TilemapTexture::Vector(cliff_image_handles);
+
+    let cliff_tile_storage = TileStorage::empty(map_size);
+    let cliff_tilemap_entity = commands.spawn_empty().id();
+
+    commands
+        .entity(cliff_tilemap_entity)
+        .insert(TilemapBundle {
+            grid_size: GRID_SIZE,
+            size: map_size,
+            storage: cliff_tile_storage,
+            texture: cliff_texture_vec,
+            tile_size: TILE_SIZE,
+            map_type: MAP_TYPE,
+            anchor: TilemapAnchor::Center,
+            transform: Transform::from_xyz(0.0, 0.0, CliffLayer::Z_INDEX),
+            ..Default::default()
+        })
+        .insert(CliffLayer);
+
     // Spawn terrain features layer.
 
     let terrain_features_image_handles = vec![
         asset_server.load("tiles/woods.png"),
         asset_server.load("tiles/rainforest.png"),
-        // TODO: marsh
-        asset_server.load("tiles/transparent.png"),
-        // TODO: floodplains
-        asset_server.load("tiles/transparent.png"),
+        asset_server.load("tiles/marsh.png"),
+        asset_server.load("tiles/floodplains.png"),
         asset_server.load("tiles/oasis.png"),
-        // TODO: cliffs
+        // Cliffs are on the edges of tiles, so they have their own layer.
         asset_server.load("tiles/transparent.png"),
         asset_server.load("tiles/ice.png"),
     ];
@@ -542,30 +1297,121 @@ pub fn spawn_tilemap(
         })
         .insert(TerrainFeaturesLayer);
 
-    commands.insert_resource(MapTerrain(terrain));
+    // Spawn resource layer.
+
+    let resource_image_handles = vec![
+        asset_server.load("tiles/resources/bananas.png"),
+        asset_server.load("tiles/resources/cattle.png"),
+        asset_server.load("tiles/resources/copper.png"),
+        asset_server.load("tiles/resources/deer.png"),
+        asset_server.load("tiles/resources/fish.png"),
+        asset_server.load("tiles/resources/rice.png"),
+        asset_server.load("tiles/resources/sheep.png"),
+        asset_server.load("tiles/resources/stone.png"),
+        asset_server.load("tiles/resources/wheat.png"),
+        asset_server.load("tiles/resources/horses.png"),
+        asset_server.load("tiles/resources/iron.png"),
+        asset_server.load("tiles/resources/niter.png"),
+        asset_server.load("tiles/resources/coal.png"),
+        asset_server.load("tiles/resources/oil.png"),
+        asset_server.load("tiles/resources/aluminum.png"),
+        asset_server.load("tiles/resources/uranium.png"),
+    ];
+    let resource_texture_vec = TilemapTexture::Vector(resource_image_handles);
+
+    let resource_tile_storage = TileStorage::empty(map_size);
+    let resource_tilemap_entity = commands.spawn_empty().id();
+
+    commands
+        .entity(resource_tilemap_entity)
+        .insert(TilemapBundle {
+            grid_size: GRID_SIZE,
+            size: map_size,
+            storage: resource_tile_storage,
+            texture: resource_texture_vec,
+            tile_size: TILE_SIZE,
+            map_type: MAP_TYPE,
+            anchor: TilemapAnchor::Center,
+            transform: Transform::from_xyz(0.0, 0.0, ResourceLayer::Z_INDEX),
+            ..Default::default()
+        })
+        .insert(ResourceLayer);
+
+    commands.insert_resource(MapMoisture(moisture));
+    commands.insert_resource(game_map);
+
+    // Spawn fog of war layer.
+
+    let fog_of_war_image_handles = vec![asset_server.load("tiles/fog.png")];
+    let fog_of_war_texture_vec = TilemapTexture::Vector(fog_of_war_image_handles);
+
+    let fog_of_war_tile_storage = TileStorage::empty(map_size);
+    let fog_of_war_tilemap_entity = commands.spawn_empty().id();
+
+    commands
+        .entity(fog_of_war_tilemap_entity)
+        .insert(TilemapBundle {
+            grid_size: GRID_SIZE,
+            size: map_size,
+            storage: fog_of_war_tile_storage,
+            texture: fog_of_war_texture_vec,
+            tile_size: TILE_SIZE,
+            map_type: MAP_TYPE,
+            anchor: TilemapAnchor::Center,
+            transform: Transform::from_xyz(0.0, 0.0, FogOfWarLayer::Z_INDEX),
+            ..Default::default()
+        })
+        .insert(FogOfWarLayer);
 
     // Spawn unit selection layer.
 
     let unit_selection_image_handles = vec![asset_server.load("units/active.png")];
     let unit_selection_texture_vec = TilemapTexture::Vector(unit_selection_image_handles);
 
-    let unit_selection_tile_storage = TileStorage::empty(map_size);
-    let unit_selection_tilemap_entity = commands.spawn_empty().id();
+    let unit_selection_tile_storage = TileStorage::empty(map_size);
+    let unit_selection_tilemap_entity = commands.spawn_empty().id();
+
+    commands
+        .entity(unit_selection_tilemap_entity)
+        .insert(TilemapBundle {
+            grid_size: GRID_SIZE,
+            size: map_size,
+            storage: unit_selection_tile_storage,
+            texture: unit_selection_texture_vec,
+            tile_size: TILE_SIZE,
+            map_type: MAP_TYPE,
+            anchor: TilemapAnchor::Center,
+            transform: Transform::from_xyz(0.0, 0.0, UnitSelectionLayer::Z_INDEX),
+            ..Default::default()
+        })
+        .insert(UnitSelectionLayer);
+
+    // Spawn movement path layer.
+
+    let movement_path_image_handles = vec![
+        asset_server.load("units/path.png"),
+        asset_server.load("units/path-turn.png"),
+        asset_server.load("units/no-path.png"),
+    ];
+    let movement_path_texture_vec = TilemapTexture::Vector(movement_path_image_handles);
+
+    let movement_path_tile_storage = TileStorage::empty(map_size);
+    let movement_path_tilemap_entity = commands.spawn_empty().id();
 
     commands
-        .entity(unit_selection_tilemap_entity)
+        .entity(movement_path_tilemap_entity)
         .insert(TilemapBundle {
             grid_size: GRID_SIZE,
             size: map_size,
-            storage: unit_selection_tile_storage,
-            texture: unit_selection_texture_vec,
+            storage: movement_path_tile_storage,
+            texture: movement_path_texture_vec,
             tile_size: TILE_SIZE,
             map_type: MAP_TYPE,
             anchor: TilemapAnchor::Center,
-            transform: Transform::from_xyz(0.0, 0.0, UnitSelectionLayer::Z_INDEX),
+            transform: Transform::from_xyz(0.0, 0.0, MovementPathLayer::Z_INDEX),
             ..Default::default()
         })
-        .insert(UnitSelectionLayer);
+        .insert(MovementPathLayer);
 
     // Spawn unit state layer.
 
@@ -578,6 +1424,9 @@ pub fn spawn_tilemap(
         asset_server.load("units/land-military-fortified-out-of-orders.png"),
         asset_server.load("units/civilian-out-of-moves.png"),
         asset_server.load("units/land-military-out-of-moves.png"),
+        asset_server.load("units/embarked-ready.png"),
+        asset_server.load("units/embarked-ready-out-of-orders.png"),
+        asset_server.load("units/embarked-out-of-moves.png"),
     ];
     let unit_state_texture_vec = TilemapTexture::Vector(unit_state_image_handles);
 
@@ -646,170 +1495,151 @@ pub fn spawn_tilemap(
         .insert(LandMilitaryUnitLayer);
 }
 
+/// Finishes the terrain of the game map, and generates the strategic
+/// resources on it.
 #[allow(clippy::too_many_arguments)]
 pub fn post_spawn_tilemap(
     mut commands: Commands,
     mut map_rng: ResMut<MapRng>,
     map_terrain: Res<MapTerrain>,
-    base_terrain_tilemap_query: Single<(&TilemapSize, &TileStorage), BaseTerrainLayerFilter>,
-    river_tilemap_query: Single<(Entity, &mut TileStorage), RiverLayerFilter>,
-    terrain_features_tilemap_query: Single<(Entity, &mut TileStorage), TerrainFeaturesLayerFilter>,
-    mut base_terrain_tile_query: Query<(&mut TileTextureIndex,), BaseTerrainLayerFilter>,
+    map_moisture: Res<MapMoisture>,
+    elevation_thresholds: Res<ElevationThresholds>,
+    map_wrapping: Res<MapWrapping>,
+    mut game_map: ResMut<GameMap>,
 ) {
     let rng = &mut map_rng.0;
-    let terrain = &map_terrain.0;
-
-    let (map_size, base_terrain_tile_storage) = base_terrain_tilemap_query.into_inner();
-    let (terrain_features_tilemap_entity, mut terrain_features_tile_storage) =
-        terrain_features_tilemap_query.into_inner();
-    let (river_tilemap_entity, mut river_tile_storage) = river_tilemap_query.into_inner();
+    let terrain = &*map_terrain;
+    let map_wrapping = *map_wrapping;
+    let game_map = &mut *game_map;
+    let map_size = &game_map.size();
+
+    // Turn enclosed bodies of water into lakes, if they are small enough.
+    let lake_positions = find_lakes(map_size, map_wrapping, |tile_pos| {
+        game_map[tile_pos].base_terrain == BaseTerrain::Ocean
+    });
+    for tile_pos in lake_positions {
+        game_map[&tile_pos].base_terrain = BaseTerrain::Lake;
+    }
 
-    let mut river_edges: Vec<RiverEdge> = vec![];
+    // Turn the ocean on the continental shelf into coast.
+    let coast_positions = find_continental_shelf(
+        map_size,
+        map_wrapping,
+        |tile_pos| game_map[tile_pos].base_terrain == BaseTerrain::Ocean,
+        |tile_pos| {
+            let tile = &game_map[tile_pos];
+            if tile.base_terrain.is_water() {
+                None
+            } else {
+                Some(tile.elevation)
+            }
+        },
+    );
+    for tile_pos in coast_positions {
+        game_map[&tile_pos].base_terrain = BaseTerrain::Coast;
+    }
 
+    // Add cliffs along the edges where hills or mountains meet the coast.
     for x in 0..map_size.x {
         for y in 0..map_size.y {
             let tile_pos = TilePos { x, y };
-            let tile_entity = base_terrain_tile_storage.get(&tile_pos).unwrap();
-            let (&tile_texture,) = base_terrain_tile_query.get(tile_entity).unwrap();
-            let neighbor_positions =
-                HexNeighbors::get_neighboring_positions_row_odd(&tile_pos, map_size);
-            let neighbor_entities = neighbor_positions.entities(base_terrain_tile_storage);
-
-            let latitude = NotNan::new(-90.0).unwrap()
-                + NotNan::new(180.0).unwrap()
-                    * ((NotNan::from(tile_pos.y) + NotNan::new(0.5).unwrap())
-                        / NotNan::from(map_size.y));
-
-            if matches!(tile_texture, TileTextureIndex(t) if t == u32::from(BaseTerrain::Ocean))
-                && neighbor_entities.iter().any(|neighbor_entity| {
-                    let (tile_texture,) = base_terrain_tile_query.get(*neighbor_entity).unwrap();
-                    ![BaseTerrain::Ocean.into(), BaseTerrain::Coast.into()]
-                        .contains(&tile_texture.0)
-                })
+            let tile = &game_map[&tile_pos];
+            if tile.base_terrain.is_water()
+                || tile.elevation < elevation_thresholds.hills_min_elevation
             {
-                let (mut tile_texture,) = base_terrain_tile_query.get_mut(tile_entity).unwrap();
-                tile_texture.0 = BaseTerrain::Coast.into();
+                continue;
+            }
+
+            let neighbor_positions = neighboring_positions(&tile_pos, map_size, map_wrapping);
+            let mut cliff_hex_edges: CliffHexEdges = BitArray::<_>::ZERO;
+            for (i, &direction) in HEX_DIRECTIONS.iter().enumerate() {
+                let Some(neighbor_pos) = neighbor_positions.get(direction) else {
+                    continue;
+                };
+                if [BaseTerrain::Coast, BaseTerrain::Ocean]
+                    .contains(&game_map[neighbor_pos].base_terrain)
+                {
+                    cliff_hex_edges.set(i, true);
+                }
             }
+            game_map[&tile_pos].cliff_hex_edges = cliff_hex_edges;
+        }
+    }
+
+    let mut river_edges: Vec<RiverEdge> = vec![];
+
+    for x in 0..map_size.x {
+        for y in 0..map_size.y {
+            let tile_pos = TilePos { x, y };
+            let base_terrain = game_map[&tile_pos].base_terrain;
+            let neighbor_positions = neighboring_positions(&tile_pos, map_size, map_wrapping);
+
+            let latitude = tile_latitude(&tile_pos, map_size);
 
             if [
-                BaseTerrain::Plains.into(),
-                BaseTerrain::PlainsHills.into(),
-                BaseTerrain::Grassland.into(),
-                BaseTerrain::GrasslandHills.into(),
-                BaseTerrain::Tundra.into(),
-                BaseTerrain::TundraHills.into(),
+                BaseTerrain::Plains,
+                BaseTerrain::PlainsHills,
+                BaseTerrain::Grassland,
+                BaseTerrain::GrasslandHills,
+                BaseTerrain::Tundra,
+                BaseTerrain::TundraHills,
             ]
-            .contains(&tile_texture.0)
+            .contains(&base_terrain)
                 && rng.choice(WOODS_CHOICES).unwrap()
             {
-                let tile_entity = commands
-                    .spawn(TileBundle {
-                        position: tile_pos,
-                        tilemap_id: TilemapId(terrain_features_tilemap_entity),
-                        texture_index: TileTextureIndex(TerrainFeatures::Woods.into()),
-                        ..Default::default()
-                    })
-                    .insert(TerrainFeaturesLayer)
-                    .id();
-                terrain_features_tile_storage.set(&tile_pos, tile_entity);
-            } else if [BaseTerrain::Plains.into(), BaseTerrain::PlainsHills.into()]
-                .contains(&tile_texture.0)
-                && {
-                    if *latitude >= EarthLatitude::ArticCirle.latitude()
-                        || *latitude <= EarthLatitude::AntarcticCircle.latitude()
-                    {
-                        false
-                    } else if *latitude >= 35.0 || *latitude <= -35.0 {
-                        rng.choice(TEMPERATE_RAINFOREST_CHOICES).unwrap()
-                    } else if *latitude >= EarthLatitude::TropicOfCancer.latitude()
-                        || *latitude <= EarthLatitude::TropicOfCapricorn.latitude()
-                    {
-                        rng.choice(SUBTROPICAL_RAINFOREST_CHOICES).unwrap()
-                    } else {
-                        rng.choice(TROPICAL_RAINFOREST_CHOICES).unwrap()
-                    }
+                game_map[&tile_pos].terrain_features = Some(TerrainFeatures::Woods);
+            } else if [BaseTerrain::Plains, BaseTerrain::PlainsHills].contains(&base_terrain) && {
+                if *latitude >= EarthLatitude::ArticCirle.latitude()
+                    || *latitude <= EarthLatitude::AntarcticCircle.latitude()
+                {
+                    false
+                } else if *latitude >= 35.0 || *latitude <= -35.0 {
+                    rng.choice(TEMPERATE_RAINFOREST_CHOICES).unwrap()
+                } else if *latitude >= EarthLatitude::TropicOfCancer.latitude()
+                    || *latitude <= EarthLatitude::TropicOfCapricorn.latitude()
+                {
+                    rng.choice(SUBTROPICAL_RAINFOREST_CHOICES).unwrap()
+                } else {
+                    rng.choice(TROPICAL_RAINFOREST_CHOICES).unwrap()
                 }
-            {
-                let tile_entity = commands
-                    .spawn(TileBundle {
-                        position: tile_pos,
-                        tilemap_id: TilemapId(terrain_features_tilemap_entity),
-                        texture_index: TileTextureIndex(TerrainFeatures::Rainforest.into()),
-                        ..Default::default()
-                    })
-                    .insert(TerrainFeaturesLayer)
-                    .id();
-                terrain_features_tile_storage.set(&tile_pos, tile_entity);
+            } {
+                game_map[&tile_pos].terrain_features = Some(TerrainFeatures::Rainforest);
             }
 
-            if matches!(tile_texture, TileTextureIndex(t) if t == u32::from(BaseTerrain::Desert))
-                && rng.choice(OASIS_CHOICES).unwrap()
-            {
-                let tile_entity = commands
-                    .spawn(TileBundle {
-                        position: tile_pos,
-                        tilemap_id: TilemapId(terrain_features_tilemap_entity),
-                        texture_index: TileTextureIndex(TerrainFeatures::Oasis.into()),
-                        ..Default::default()
-                    })
-                    .insert(TerrainFeaturesLayer)
-                    .id();
-                terrain_features_tile_storage.set(&tile_pos, tile_entity);
+            if base_terrain == BaseTerrain::Desert && rng.choice(OASIS_CHOICES).unwrap() {
+                game_map[&tile_pos].terrain_features = Some(TerrainFeatures::Oasis);
             }
 
-            if [BaseTerrain::Ocean.into(), BaseTerrain::Coast.into()].contains(&tile_texture.0)
+            if [BaseTerrain::Ocean, BaseTerrain::Coast].contains(&base_terrain)
                 && (*latitude >= EarthLatitude::ArticCirle.latitude()
                     || *latitude <= EarthLatitude::AntarcticCircle.latitude())
                 && rng.choice(ICE_CHOICES).unwrap()
             {
-                let tile_entity = commands
-                    .spawn(TileBundle {
-                        position: tile_pos,
-                        tilemap_id: TilemapId(terrain_features_tilemap_entity),
-                        texture_index: TileTextureIndex(TerrainFeatures::Ice.into()),
-                        ..Default::default()
-                    })
-                    .insert(TerrainFeaturesLayer)
-                    .id();
-                terrain_features_tile_storage.set(&tile_pos, tile_entity);
+                game_map[&tile_pos].terrain_features = Some(TerrainFeatures::Ice);
             }
 
             if ![
-                BaseTerrain::Ocean.into(),
-                BaseTerrain::Coast.into(),
+                BaseTerrain::Ocean,
+                BaseTerrain::Coast,
+                BaseTerrain::Lake,
                 // Exclude lowlands and deserts as river source.
-                BaseTerrain::Plains.into(),
-                BaseTerrain::Grassland.into(),
-                BaseTerrain::Desert.into(),
-                BaseTerrain::DesertHills.into(),
-                BaseTerrain::DesertMountains.into(),
-                BaseTerrain::Tundra.into(),
-                BaseTerrain::Snow.into(),
+                BaseTerrain::Plains,
+                BaseTerrain::Grassland,
+                BaseTerrain::Desert,
+                BaseTerrain::DesertHills,
+                BaseTerrain::DesertMountains,
+                BaseTerrain::Tundra,
+                BaseTerrain::Snow,
             ]
-            .contains(&tile_texture.0)
+            .contains(&base_terrain)
             {
                 let mut vertex_elevations: Vec<_> = chain(VERTEX_OFFSETS, EXTENDED_VERTEX_OFFSETS)
-                    .map(|(vertex_offset_x, vertex_offset_y)| {
-                        let x = BOUND_MIN.x
-                            + (f64::from(GRID_SIZE.x) / 2.0
-                                + f64::from(tile_pos.x) * CENTER_TO_CENTER_X
-                                + if tile_pos.y % 2 == 0 {
-                                    0.0
-                                } else {
-                                    ODD_ROW_OFFSET
-                                }
-                                + f64::from(vertex_offset_x))
-                                / 100.0;
-                        let y = BOUND_MIN.y
-                            + (f64::from(GRID_SIZE.y) / 2.0
-                                + f64::from(map_size.y - tile_pos.y - 1) * CENTER_TO_CENTER_Y
-                                + f64::from(vertex_offset_y))
-                                / 100.0;
-                        let site = Site2D { x, y };
-                        terrain
-                            .get_elevation(&site)
-                            .filter(|elevation| !elevation.is_nan())
-                            .map(|elevation| NotNan::new(elevation).unwrap())
+                    .map(|vertex_offset| {
+                        terrain.vertex_elevation(&Vertex::of_tile(
+                            &tile_pos,
+                            map_size,
+                            vertex_offset,
+                        ))
                     })
                     .collect();
                 let extended_vertex_elevations = vertex_elevations.split_off(6);
@@ -848,28 +1678,16 @@ pub fn post_spawn_tilemap(
 
                 let edge_a = vertex_min;
                 let edge_b = (edge_a + 1) % 6;
-                if let Some(edge_adjacent_tile_entity) =
-                    neighbor_entities.get(HEX_DIRECTIONS[edge_a])
+                if let Some(edge_adjacent_tile_pos) = neighbor_positions.get(HEX_DIRECTIONS[edge_a])
                 {
-                    let (tile_texture,) = base_terrain_tile_query
-                        .get(*edge_adjacent_tile_entity)
-                        .unwrap();
-                    if [BaseTerrain::Ocean.into(), BaseTerrain::Coast.into()]
-                        .contains(&tile_texture.0)
-                    {
+                    if game_map[edge_adjacent_tile_pos].base_terrain.is_water() {
                         // Avoid creating river edges parallel to the sea shore / lake shore.
                         continue;
                     }
                 }
-                if let Some(edge_adjacent_tile_entity) =
-                    neighbor_entities.get(HEX_DIRECTIONS[edge_b])
+                if let Some(edge_adjacent_tile_pos) = neighbor_positions.get(HEX_DIRECTIONS[edge_b])
                 {
-                    let (tile_texture,) = base_terrain_tile_query
-                        .get(*edge_adjacent_tile_entity)
-                        .unwrap();
-                    if [BaseTerrain::Ocean.into(), BaseTerrain::Coast.into()]
-                        .contains(&tile_texture.0)
-                    {
+                    if game_map[edge_adjacent_tile_pos].base_terrain.is_water() {
                         // Avoid creating river edges parallel to the sea shore / lake shore.
                         continue;
                     }
@@ -917,7 +1735,7 @@ pub fn post_spawn_tilemap(
                         .try_into()
                         .expect("`(source, destination)` should match a valid offset");
                     let neighbor_positions =
-                        HexNeighbors::get_neighboring_positions_row_odd(&tile_pos, map_size);
+                        neighboring_positions(&tile_pos, map_size, map_wrapping);
                     let edge_a = vertex_direction as usize;
                     let edge_b = (edge_a + 1) % 6;
 
@@ -927,14 +1745,20 @@ pub fn post_spawn_tilemap(
                             HexCoordSystem::RowOdd,
                         );
                         let neighbor_positions =
-                            HexNeighbors::get_neighboring_positions_row_odd(&tile_pos_a, map_size);
+                            neighboring_positions(&tile_pos_a, map_size, map_wrapping);
                         if let Some(&tile_pos_aa) = neighbor_positions.get(HEX_DIRECTIONS[edge_a]) {
                             let axial_pos_aa = AxialPos::from_tile_pos_given_coord_system(
                                 &tile_pos_aa,
                                 HexCoordSystem::RowOdd,
                             );
-                            let confluence_destination =
-                                source + HexVertexDirection::OFFSETS[(edge_a + 2) % 6];
+                            let (_, confluence_destination) = wrap_river_edge(
+                                source
+                                    .offset(HEX_DIRECTIONS[edge_a])
+                                    .offset(HEX_DIRECTIONS[edge_a]),
+                                source + HexVertexDirection::OFFSETS[(edge_a + 2) % 6],
+                                map_size,
+                                map_wrapping,
+                            );
                             debug!(
                                 ?tile_pos,
                                 ?vertex_direction,
@@ -952,8 +1776,12 @@ pub fn post_spawn_tilemap(
                                 })
                             {
                                 // debug!(?confluence_river_edge, "found river confluence");
-                                let merged_destination =
-                                    source + HexVertexDirection::OFFSETS[(edge_a + 1) % 6];
+                                let (_, merged_destination) = wrap_river_edge(
+                                    source.offset(HEX_DIRECTIONS[edge_a]),
+                                    source + HexVertexDirection::OFFSETS[(edge_a + 1) % 6],
+                                    map_size,
+                                    map_wrapping,
+                                );
                                 if river_edges.iter().any(|river_edge| {
                                     river_edge.source == axial_pos_a
                                         && river_edge.destination == merged_destination
@@ -981,14 +1809,20 @@ pub fn post_spawn_tilemap(
                             HexCoordSystem::RowOdd,
                         );
                         let neighbor_positions =
-                            HexNeighbors::get_neighboring_positions_row_odd(&tile_pos_b, map_size);
+                            neighboring_positions(&tile_pos_b, map_size, map_wrapping);
                         if let Some(&tile_pos_bb) = neighbor_positions.get(HEX_DIRECTIONS[edge_b]) {
                             let axial_pos_bb = AxialPos::from_tile_pos_given_coord_system(
                                 &tile_pos_bb,
                                 HexCoordSystem::RowOdd,
                             );
-                            let confluence_destination =
-                                source + HexVertexDirection::OFFSETS[(edge_b + 3) % 6];
+                            let (_, confluence_destination) = wrap_river_edge(
+                                source
+                                    .offset(HEX_DIRECTIONS[edge_b])
+                                    .offset(HEX_DIRECTIONS[edge_b]),
+                                source + HexVertexDirection::OFFSETS[(edge_b + 3) % 6],
+                                map_size,
+                                map_wrapping,
+                            );
                             // debug!(
                             //     ?tile_pos,
                             //     ?vertex_direction,
@@ -1006,8 +1840,12 @@ pub fn post_spawn_tilemap(
                                 })
                             {
                                 // debug!(?confluence_river_edge, "found river confluence");
-                                let merged_destination =
-                                    source + HexVertexDirection::OFFSETS[(edge_b + 4) % 6];
+                                let (_, merged_destination) = wrap_river_edge(
+                                    source.offset(HEX_DIRECTIONS[edge_b]),
+                                    source + HexVertexDirection::OFFSETS[(edge_b + 4) % 6],
+                                    map_size,
+                                    map_wrapping,
+                                );
                                 if river_edges.iter().any(|river_edge| {
                                     river_edge.source == axial_pos_b
                                         && river_edge.destination == merged_destination
@@ -1037,102 +1875,838 @@ pub fn post_spawn_tilemap(
 
     let mut river_edges: Vec<Option<RiverEdge>> = river_edges.into_iter().map(Some).collect();
 
-    // Recursively group adjacent river edges.
-    for river_edge in river_edges.iter_mut() {
-        // TODO: Actually perform grouping.
-        grouped_river_edges.push(vec![river_edge.take().unwrap()]);
+    // Group river edges which are connected through their vertices.
+    while let Some(river_edge) = river_edges.iter_mut().find_map(Option::take) {
+        let mut group = vec![river_edge];
+        let mut i = 0;
+        while let Some(river_edge) = group.get(i) {
+            let vertices = [
+                river_edge.upstream_vertex(map_size, map_wrapping),
+                river_edge.downstream_vertex(map_size, map_wrapping),
+            ];
+            for other_river_edge in river_edges.iter_mut() {
+                if other_river_edge.is_some_and(|other_river_edge| {
+                    vertices.contains(&other_river_edge.upstream_vertex(map_size, map_wrapping))
+                        || vertices
+                            .contains(&other_river_edge.downstream_vertex(map_size, map_wrapping))
+                }) {
+                    group.push(other_river_edge.take().unwrap());
+                }
+            }
+            i += 1;
+        }
+        grouped_river_edges.push(group);
     }
 
     // Remove rivers which are too short.
-    // grouped_river_edges.retain(|river_edges| river_edges.len() >=
-    // River::MIN_LEN);
+    let rivers: Vec<River> = grouped_river_edges
+        .into_iter()
+        .map(|river_edges| River::new(river_edges, map_size, map_wrapping))
+        .filter(|river| river.len() >= River::MIN_LEN)
+        .collect();
+
+    debug!(num_rivers = rivers.len(), "generated rivers");
 
-    // debug!(?grouped_river_edges, "generated rivers");
+    // The highest stream order of the river edges around each tile.
+    let mut river_stream_order_map: HashMap<TilePos, StreamOrder> = HashMap::new();
+    let mut map_rivers: HashMap<TilePos, Vec<Entity>> = HashMap::new();
 
-    let mut river_hex_edges_map: HashMap<TilePos, RiverHexEdges> = HashMap::new();
+    let mut river_names = RIVER_NAMES;
+    rng.shuffle(&mut river_names);
 
-    for river_edges in grouped_river_edges {
-        for RiverEdge {
+    for (i, river) in rivers.into_iter().enumerate() {
+        let mut river_tile_positions = vec![];
+
+        for &RiverEdge {
             source,
             destination,
-            ..
-        } in river_edges
+            stream_order,
+        } in &river.edges
         {
             let tile_pos = source.as_tile_pos_given_coord_system(HexCoordSystem::RowOdd);
             let vertex_direction: HexVertexDirection = (source, destination)
                 .try_into()
                 .expect("`(source, destination)` should match a valid offset");
-            let neighbor_positions =
-                HexNeighbors::get_neighboring_positions_row_odd(&tile_pos, map_size);
+            let neighbor_positions = neighboring_positions(&tile_pos, map_size, map_wrapping);
 
             let edge_a = vertex_direction as usize;
             let edge_b = (edge_a + 1) % 6;
 
             if let Some(tile_pos) = neighbor_positions.get(HEX_DIRECTIONS[edge_a]) {
-                let river_hex_edges = river_hex_edges_map
-                    .entry(*tile_pos)
-                    .or_insert(BitArray::<_>::ZERO);
                 let river_edge = (edge_a + 2) % 6;
-                river_hex_edges.set(river_edge, true);
+                game_map[tile_pos].river_hex_edges.set(river_edge, true);
+                river_tile_positions.push(*tile_pos);
+                river_stream_order_map
+                    .entry(*tile_pos)
+                    .and_modify(|max_stream_order| {
+                        *max_stream_order = (*max_stream_order).max(stream_order)
+                    })
+                    .or_insert(stream_order);
             }
 
             if let Some(tile_pos) = neighbor_positions.get(HEX_DIRECTIONS[edge_b]) {
-                let river_hex_edges = river_hex_edges_map
-                    .entry(*tile_pos)
-                    .or_insert(BitArray::<_>::ZERO);
                 let river_edge = (edge_b + 4) % 6;
-                river_hex_edges.set(river_edge, true);
+                game_map[tile_pos].river_hex_edges.set(river_edge, true);
+                river_tile_positions.push(*tile_pos);
+                river_stream_order_map
+                    .entry(*tile_pos)
+                    .and_modify(|max_stream_order| {
+                        *max_stream_order = (*max_stream_order).max(stream_order)
+                    })
+                    .or_insert(stream_order);
+            }
+        }
+
+        // Reuse the names with a numeral once all of them have been given out.
+        let river_name = river_names[i % river_names.len()];
+        let river_name = match i / river_names.len() {
+            0 => river_name.to_owned(),
+            n => format!("{river_name} {}", n + 1),
+        };
+        let river_entity = commands.spawn((Name::new(river_name), river)).id();
+
+        for tile_pos in river_tile_positions.into_iter().unique() {
+            map_rivers.entry(tile_pos).or_default().push(river_entity);
+        }
+    }
+
+    commands.insert_resource(MapRivers(map_rivers));
+
+    // Add floodplains on flat desert, plains and grassland along rivers. The
+    // larger the river, the more likely it floods.
+    for (&tile_pos, &stream_order) in river_stream_order_map.iter().sorted() {
+        let tile = &game_map[&tile_pos];
+
+        if [
+            BaseTerrain::Desert,
+            BaseTerrain::Plains,
+            BaseTerrain::Grassland,
+        ]
+        .contains(&tile.base_terrain)
+            && tile.terrain_features.is_none()
+            && match stream_order {
+                StreamOrder(1) => rng.choice(MINOR_RIVER_FLOODPLAINS_CHOICES).unwrap(),
+                StreamOrder(2) => rng.choice(MAJOR_RIVER_FLOODPLAINS_CHOICES).unwrap(),
+                _ => true,
+            }
+        {
+            game_map[&tile_pos].terrain_features = Some(TerrainFeatures::Floodplains);
+        }
+    }
+
+    // Add marsh on low-lying, wet grassland next to rivers, lakes or coast.
+    for x in 0..map_size.x {
+        for y in 0..map_size.y {
+            let tile_pos = TilePos { x, y };
+            let tile = &game_map[&tile_pos];
+            let neighbor_positions = neighboring_positions(&tile_pos, map_size, map_wrapping);
+
+            if tile.base_terrain == BaseTerrain::Grassland
+                && tile.terrain_features.is_none()
+                && tile.elevation < elevation_thresholds.marsh_max_elevation
+                && map_moisture
+                    .get(&tile_pos)
+                    .is_some_and(|moisture| *moisture >= MARSH_MIN_MOISTURE)
+                && (tile.river_hex_edges.any()
+                    || neighbor_positions.iter().any(|neighbor_pos| {
+                        [BaseTerrain::Coast, BaseTerrain::Lake]
+                            .contains(&game_map[neighbor_pos].base_terrain)
+                    }))
+                && rng.choice(MARSH_CHOICES).unwrap()
+            {
+                game_map[&tile_pos].terrain_features = Some(TerrainFeatures::Marsh);
             }
         }
     }
 
-    for (tile_pos, river_hex_edges) in river_hex_edges_map {
+    // Generate strategic resources, now that the terrain is final.
+    let strategic_resources = generate_strategic_resources(
+        rng,
+        map_size,
+        map_wrapping,
+        |tile_pos| game_map[tile_pos].base_terrain,
+        |tile_pos| game_map[tile_pos].terrain_features,
+    );
+
+    debug!(
+        num_strategic_resources = strategic_resources.len(),
+        "generated strategic resources"
+    );
+
+    commands.insert_resource(MapStrategicResources::new(strategic_resources));
+}
+
+/// Spawns the tiles of the terrain layers, derived from the game map.
+pub fn spawn_terrain_tiles(
+    mut commands: Commands,
+    game_map: Res<GameMap>,
+    base_terrain_tilemap_query: Single<(Entity, &mut TileStorage), BaseTerrainLayerFilter>,
+    river_tilemap_query: Single<(Entity, &mut TileStorage), RiverLayerFilter>,
+    cliff_tilemap_query: Single<(Entity, &mut TileStorage), CliffLayerFilter>,
+    terrain_features_tilemap_query: Single<(Entity, &mut TileStorage), TerrainFeaturesLayerFilter>,
+) {
+    let (base_terrain_tilemap_entity, mut base_terrain_tile_storage) =
+        base_terrain_tilemap_query.into_inner();
+    let (river_tilemap_entity, mut river_tile_storage) = river_tilemap_query.into_inner();
+    let (cliff_tilemap_entity, mut cliff_tile_storage) = cliff_tilemap_query.into_inner();
+    let (terrain_features_tilemap_entity, mut terrain_features_tile_storage) =
+        terrain_features_tilemap_query.into_inner();
+
+    for (tile_pos, tile) in game_map.iter() {
         let tile_entity = commands
             .spawn(TileBundle {
                 position: tile_pos,
-                tilemap_id: TilemapId(river_tilemap_entity),
-                texture_index: TileTextureIndex(river_hex_edges.load()),
+                tilemap_id: TilemapId(base_terrain_tilemap_entity),
+                texture_index: base_terrain_texture_index(tile.base_terrain),
                 ..Default::default()
             })
-            .insert(RiverLayer)
+            .insert(BaseTerrainLayer)
             .id();
-        river_tile_storage.set(&tile_pos, tile_entity);
+        base_terrain_tile_storage.set(&tile_pos, tile_entity);
+
+        if let Some(terrain_features) = tile.terrain_features {
+            let tile_entity = commands
+                .spawn(TileBundle {
+                    position: tile_pos,
+                    tilemap_id: TilemapId(terrain_features_tilemap_entity),
+                    texture_index: terrain_features_texture_index(terrain_features),
+                    ..Default::default()
+                })
+                .insert(TerrainFeaturesLayer)
+                .id();
+            terrain_features_tile_storage.set(&tile_pos, tile_entity);
+        }
+
+        // The river and cliff textures are indexed by the bits of their hex edges.
+        if tile.river_hex_edges.any() {
+            let tile_entity = commands
+                .spawn(TileBundle {
+                    position: tile_pos,
+                    tilemap_id: TilemapId(river_tilemap_entity),
+                    texture_index: TileTextureIndex(tile.river_hex_edges.load()),
+                    ..Default::default()
+                })
+                .insert(RiverLayer)
+                .id();
+            river_tile_storage.set(&tile_pos, tile_entity);
+        }
+
+        if tile.cliff_hex_edges.any() {
+            let tile_entity = commands
+                .spawn(TileBundle {
+                    position: tile_pos,
+                    tilemap_id: TilemapId(cliff_tilemap_entity),
+                    texture_index: TileTextureIndex(tile.cliff_hex_edges.load()),
+                    ..Default::default()
+                })
+                .insert(CliffLayer)
+                .id();
+            cliff_tile_storage.set(&tile_pos, tile_entity);
+        }
+    }
+}
+
+/// Chooses the starting position of each player at random, among the land
+/// tiles with enough allowable neighbors.
+///
+/// The bonus resources are later balanced around these starting positions.
+pub fn choose_starting_positions(
+    mut commands: Commands,
+    mut map_rng: ResMut<MapRng>,
+    num_players: Res<NumPlayers>,
+    map_wrapping: Res<MapWrapping>,
+    game_map: Res<GameMap>,
+) {
+    let rng = &mut map_rng.0;
+    let map_wrapping = *map_wrapping;
+    let map_size = &game_map.size();
+
+    let is_allowable = |tile_pos: &TilePos| {
+        let base_terrain = game_map[tile_pos].base_terrain;
+        !base_terrain.is_water() && !base_terrain.is_mountains()
+    };
+
+    // Leave room around the settler for the other starting units.
+    let allowable_starting_positions: Vec<_> = (0..map_size.x)
+        .cartesian_product(0..map_size.y)
+        .map(|(x, y)| TilePos { x, y })
+        .filter(|tile_pos| {
+            is_allowable(tile_pos)
+                && neighboring_positions(tile_pos, map_size, map_wrapping)
+                    .iter()
+                    .filter(|neighbor_pos| is_allowable(neighbor_pos))
+                    .count()
+                    >= STARTING_POSITION_MIN_ALLOWABLE_NEIGHBORS
+        })
+        .collect();
+
+    let mut starting_positions: Vec<TilePos> = vec![];
+    for _ in 0..num_players.0 {
+        // TODO: Space out the starting positions for different civs.
+        let remaining_positions: Vec<_> = allowable_starting_positions
+            .iter()
+            .filter(|tile_pos| !starting_positions.contains(tile_pos))
+            .collect();
+        let starting_position = rng
+            .choice(remaining_positions)
+            .copied()
+            .expect("the map should have enough land tiles for the starting positions");
+        starting_positions.push(starting_position);
     }
+
+    debug!(?starting_positions, "chose starting positions");
+
+    commands.insert_resource(StartingPositions(starting_positions));
 }
 
-pub fn upgrade_camera(mut commands: Commands, camera_query: Single<(Entity,), With<Camera2d>>) {
+pub fn upgrade_camera(
+    mut commands: Commands,
+    map_size: Res<MapSize>,
+    map_wrapping: Res<MapWrapping>,
+    camera_query: Single<(Entity,), (With<Camera2d>, Without<WrapAroundCamera>)>,
+) {
     let (camera_entity,) = camera_query.into_inner();
 
+    let map_size = map_size.tilemap_size();
+
+    let (min_x, max_x) = if map_wrapping.wraps_east_west() {
+        // The camera can pan around the map endlessly.
+        (f32::NEG_INFINITY, f32::INFINITY)
+    } else {
+        let max_x = ((map_size.x - 1) as f64 * CENTER_TO_CENTER_X
+            + GRID_SIZE.x as f64
+            + ODD_ROW_OFFSET) as f32;
+        (-max_x, max_x)
+    };
+
     commands.entity(camera_entity).insert(PanCam {
         grab_buttons: vec![MouseButton::Left],
         move_keys: DirectionKeys::arrows_and_wasd(),
         zoom_to_cursor: true,
         min_scale: 1.0,
         max_scale: 8.0,
-        min_x: -((MAP_SIDE_LENGTH_X - 1) as f64 * CENTER_TO_CENTER_X
-            + GRID_SIZE.x as f64
-            + ODD_ROW_OFFSET) as f32,
-        max_x: ((MAP_SIDE_LENGTH_X - 1) as f64 * CENTER_TO_CENTER_X
-            + GRID_SIZE.x as f64
-            + ODD_ROW_OFFSET) as f32,
-        min_y: -((MAP_SIDE_LENGTH_Y - 1) as f64 * CENTER_TO_CENTER_Y + GRID_SIZE.y as f64) as f32,
-        max_y: ((MAP_SIDE_LENGTH_Y - 1) as f64 * CENTER_TO_CENTER_Y + GRID_SIZE.y as f64) as f32,
+        min_x,
+        max_x,
+        min_y: -((map_size.y - 1) as f64 * CENTER_TO_CENTER_Y + GRID_SIZE.y as f64) as f32,
+        max_y: ((map_size.y - 1) as f64 * CENTER_TO_CENTER_Y + GRID_SIZE.y as f64) as f32,
         ..Default::default()
     });
+
+    if map_wrapping.wraps_east_west() {
+        // Render the map once more on either side, on top of the main camera.
+        for (order, offset) in [(1, -1.0), (2, 1.0)] {
+            commands.spawn((
+                Camera2d,
+                Camera {
+                    order,
+                    clear_color: ClearColorConfig::None,
+                    ..Default::default()
+                },
+                WrapAroundCamera { offset },
+            ));
+        }
+    }
 }
 
-fn choose_base_terrain_by_latitude(rng: &mut fastrand::Rng, latitude: NotNan<f64>) -> BaseTerrain {
-    if *latitude >= EarthLatitude::ArticCirle.latitude()
-        || *latitude <= EarthLatitude::AntarcticCircle.latitude()
-    {
-        rng.choice(FRIGID_ZONE_TERRAIN_CHOICES).unwrap()
-    } else if *latitude >= 35.0 || *latitude <= -35.0 {
-        rng.choice(TEMPERATE_ZONE_TERRAIN_CHOICES).unwrap()
-    } else if *latitude >= EarthLatitude::TropicOfCancer.latitude()
-        || *latitude <= EarthLatitude::TropicOfCapricorn.latitude()
+/// Keeps the main camera over the map where the map wraps around east-west, and
+/// moves the wrap-around cameras along with it.
+#[allow(clippy::type_complexity)]
+pub fn wrap_camera_around(
+    map_size: Res<MapSize>,
+    camera_query: Single<
+        (&mut Transform, &Projection),
+        (With<Camera2d>, Without<WrapAroundCamera>),
+    >,
+    mut wrap_around_camera_query: Query<(&mut Transform, &mut Projection, &WrapAroundCamera)>,
+) {
+    let (mut camera_transform, camera_projection) = camera_query.into_inner();
+
+    let map_width = (f64::from(map_size.tilemap_size().x) * CENTER_TO_CENTER_X) as f32;
+
+    let x = camera_transform.translation.x;
+    let wrapped_x = (x + map_width / 2.0).rem_euclid(map_width) - map_width / 2.0;
+    if wrapped_x != x {
+        camera_transform.translation.x = wrapped_x;
+    }
+
+    for (mut transform, mut projection, &WrapAroundCamera { offset }) in
+        &mut wrap_around_camera_query
     {
-        rng.choice(SUBTROPICS_TERRAIN_CHOICES).unwrap()
-    } else {
-        rng.choice(TROPICS_TERRAIN_CHOICES).unwrap()
+        transform.translation = camera_transform
+            .translation
+            .with_x(camera_transform.translation.x + offset * map_width);
+        *projection = camera_projection.clone();
+    }
+}
+
+/// Returns the distance between two tiles, in number of tiles.
+///
+/// Where the map wraps around east-west, the distance is the shorter of the
+/// distances going either way around the map.
+pub fn hex_distance(
+    a: &TilePos,
+    b: &TilePos,
+    map_size: &TilemapSize,
+    map_wrapping: MapWrapping,
+) -> u32 {
+    let a = AxialPos::from_tile_pos_given_coord_system(a, HexCoordSystem::RowOdd);
+    let b = AxialPos::from_tile_pos_given_coord_system(b, HexCoordSystem::RowOdd);
+    let distance = a.distance_from(&b).unsigned_abs();
+    if !map_wrapping.wraps_east_west() {
+        return distance;
+    }
+    let map_width = map_size.x as i32;
+    [-map_width, map_width]
+        .into_iter()
+        .map(|offset| {
+            a.distance_from(&AxialPos {
+                q: b.q + offset,
+                r: b.r,
+            })
+            .unsigned_abs()
+        })
+        .fold(distance, u32::min)
+}
+
+/// Returns the positions of the neighboring tiles of the tile.
+///
+/// Where the map wraps around east-west, the tiles along the east edge of the
+/// map neighbor the tiles along the west edge.
+pub fn neighboring_positions(
+    tile_pos: &TilePos,
+    map_size: &TilemapSize,
+    map_wrapping: MapWrapping,
+) -> HexNeighbors<TilePos> {
+    if !map_wrapping.wraps_east_west() {
+        return HexNeighbors::get_neighboring_positions_row_odd(tile_pos, map_size);
+    }
+    let axial_pos = AxialPos::from_tile_pos_given_coord_system(tile_pos, HexCoordSystem::RowOdd);
+    HexNeighbors::from_directional_closure(|direction| {
+        let RowOddPos { q, r } = RowOddPos::from(axial_pos.offset(direction));
+        let y = u32::try_from(r).ok().filter(|&y| y < map_size.y)?;
+        let x = q.rem_euclid(map_size.x as i32) as u32;
+        Some(TilePos { x, y })
+    })
+}
+
+/// Returns the positions of the three tiles around the vertex of the tile, in
+/// sorted order.
+///
+/// The positions are wrapped around to within the map where the map wraps
+/// around east-west, so that the same vertex on either side of the seam
+/// compares equal.
+fn hex_vertex(
+    axial_pos: AxialPos,
+    vertex_direction: HexVertexDirection,
+    map_size: &TilemapSize,
+    map_wrapping: MapWrapping,
+) -> [AxialPos; 3] {
+    let edge_a = vertex_direction as usize;
+    let edge_b = (edge_a + 1) % 6;
+    let mut vertex = [
+        axial_pos,
+        axial_pos.offset(HEX_DIRECTIONS[edge_a]),
+        axial_pos.offset(HEX_DIRECTIONS[edge_b]),
+    ]
+    .map(|axial_pos| wrap_axial_pos(axial_pos, map_size, map_wrapping));
+    vertex.sort();
+    vertex
+}
+
+/// Returns the equivalent axial position within the east / west edges of the
+/// map, where the map wraps around east-west.
+fn wrap_axial_pos(
+    axial_pos: AxialPos,
+    map_size: &TilemapSize,
+    map_wrapping: MapWrapping,
+) -> AxialPos {
+    if !map_wrapping.wraps_east_west() {
+        return axial_pos;
+    }
+    let RowOddPos { q, r } = RowOddPos::from(axial_pos);
+    AxialPos::from(RowOddPos {
+        q: q.rem_euclid(map_size.x as i32),
+        r,
+    })
+}
+
+/// Returns the source and the destination of a river edge, shifted together so
+/// that the source is within the east / west edges of the map, where the map
+/// wraps around east-west.
+fn wrap_river_edge(
+    source: AxialPos,
+    destination: AxialPos,
+    map_size: &TilemapSize,
+    map_wrapping: MapWrapping,
+) -> (AxialPos, AxialPos) {
+    let wrapped_source = wrap_axial_pos(source, map_size, map_wrapping);
+    (wrapped_source, destination + (wrapped_source - source))
+}
+
+/// Chooses the centers and the radius of the landmasses for the map script.
+///
+/// The landmass centers are in [`Site2D`] coordinates.
+fn choose_landmasses(
+    rng: &mut fastrand::Rng,
+    map_script: MapScript,
+    map_size: &TilemapSize,
+    bound_range: Site2D,
+) -> (Vec<Site2D>, f64) {
+    match map_script {
+        MapScript::Continents => {
+            let num_continents = rng.usize(CONTINENTS_NUM_CONTINENTS);
+            let landmass_centers = (0..num_continents)
+                .map(|i| Site2D {
+                    x: bound_range.x * ((i as f64 + 0.5) / num_continents as f64 - 0.5),
+                    y: bound_range.y * rng.f64_range(-0.15..=0.15),
+                })
+                .collect();
+            // Leave a gap between the continents.
+            let landmass_radius = bound_range.x / num_continents as f64 * 0.6;
+            (landmass_centers, landmass_radius)
+        },
+        MapScript::Pangaea => {
+            // Line up the centers so that they merge into one elongated landmass.
+            let landmass_centers = [-1.0, 0.0, 1.0]
+                .into_iter()
+                .map(|offset| Site2D {
+                    x: bound_range.x * offset / 6.0,
+                    y: bound_range.y * rng.f64_range(-0.1..=0.1),
+                })
+                .collect();
+            let landmass_radius = bound_range.y * 0.6;
+            (landmass_centers, landmass_radius)
+        },
+        MapScript::Islands => {
+            let num_islands = (map_size.count() / ISLANDS_TILES_PER_ISLAND).max(1);
+            let landmass_centers = (0..num_islands)
+                .map(|_| Site2D {
+                    x: bound_range.x * rng.f64_range(-0.425..=0.425),
+                    y: bound_range.y * rng.f64_range(-0.4..=0.4),
+                })
+                .collect();
+            let landmass_radius = bound_range.y * 0.15;
+            (landmass_centers, landmass_radius)
+        },
+        MapScript::Fractal => (vec![], f64::INFINITY),
+    }
+}
+
+/// Returns the size of the map in [`Site2D`] coordinates, with the map centered
+/// on the origin.
+fn bound_range(map_size: &TilemapSize) -> Site2D {
+    Site2D {
+        x: ((map_size.x - 1) as f64 * CENTER_TO_CENTER_X + GRID_SIZE.x as f64 + ODD_ROW_OFFSET)
+            / 100.0,
+        y: ((map_size.y - 1) as f64 * CENTER_TO_CENTER_Y + GRID_SIZE.y as f64) / 100.0,
+    }
+}
+
+/// Returns the width of the map in [`Site2D`] coordinates, i.e. the distance
+/// after which the map repeats itself where it wraps around east-west.
+fn wrap_width(map_size: &TilemapSize) -> f64 {
+    f64::from(map_size.x) * CENTER_TO_CENTER_X / 100.0
+}
+
+/// Returns the elevation of the tile, i.e. the mean elevation of its vertices.
+fn tile_elevation(terrain: &MapTerrain, tile_pos: &TilePos, map_size: &TilemapSize) -> NotNan<f64> {
+    let elevations: Vec<_> = VERTEX_OFFSETS
+        .into_iter()
+        .flat_map(|vertex_offset| {
+            terrain.vertex_elevation(&Vertex::of_tile(tile_pos, map_size, vertex_offset))
+        })
+        .collect();
+    elevations.iter().sum::<NotNan<_>>()
+        / NotNan::from(
+            u8::try_from(elevations.len()).expect("`elevations.len()` should fit in `u8`"),
+        )
+}
+
+/// Returns the latitude at the center of the tile.
+fn tile_latitude(tile_pos: &TilePos, map_size: &TilemapSize) -> NotNan<f64> {
+    NotNan::new(-90.0).unwrap()
+        + NotNan::new(180.0).unwrap()
+            * ((NotNan::from(tile_pos.y) + NotNan::new(0.5).unwrap()) / NotNan::from(map_size.y))
+}
+
+/// Generates the moisture of each land tile.
+///
+/// The moisture is made up of:
+///
+/// * noise, so that humid and arid regions form coherent biomes;
+/// * the distance from the ocean, as moisture is carried inland from the sea;
+/// * the rain shadow downwind of mountains, as the prevailing winds lose their
+///   moisture on the windward side.
+fn generate_moisture(
+    rng: &mut fastrand::Rng,
+    map_size: &TilemapSize,
+    map_wrapping: MapWrapping,
+    elevations: &HashMap<TilePos, NotNan<f64>>,
+    mountains_min_elevation: NotNan<f64>,
+) -> HashMap<TilePos, NotNan<f64>> {
+    let perlin = Perlin::new(rng.u32(..));
+
+    // Find the distance from each tile to the nearest water tile.
+    let mut water_distances: HashMap<TilePos, u32> = HashMap::new();
+    let mut queue: VecDeque<TilePos> = elevations
+        .iter()
+        .filter(|(_, elevation)| ***elevation < SEA_LEVEL)
+        .map(|(&tile_pos, _)| tile_pos)
+        .sorted()
+        .collect();
+    for &tile_pos in &queue {
+        water_distances.insert(tile_pos, 0);
+    }
+    while let Some(tile_pos) = queue.pop_front() {
+        let distance = water_distances[&tile_pos];
+        let neighbor_positions = neighboring_positions(&tile_pos, map_size, map_wrapping);
+        for &neighbor_pos in neighbor_positions.iter() {
+            if let Entry::Vacant(entry) = water_distances.entry(neighbor_pos) {
+                entry.insert(distance + 1);
+                queue.push_back(neighbor_pos);
+            }
+        }
+    }
+
+    elevations
+        .iter()
+        .filter(|(_, elevation)| ***elevation >= SEA_LEVEL)
+        .map(|(&tile_pos, _)| {
+            // The center of the tile lies on the same grid as its vertices.
+            let site = Vertex::of_tile(&tile_pos, map_size, (0, 0)).site(map_size);
+            let noise = if map_wrapping.wraps_east_west() {
+                // Sample the noise around a cylinder, so that it wraps around seamlessly.
+                let radius = wrap_width(map_size) / TAU;
+                let angle = site.x / radius;
+                perlin.get([
+                    radius * angle.cos() * MOISTURE_NOISE_SCALE,
+                    radius * angle.sin() * MOISTURE_NOISE_SCALE,
+                    site.y * MOISTURE_NOISE_SCALE,
+                ])
+            } else {
+                perlin.get([site.x * MOISTURE_NOISE_SCALE, site.y * MOISTURE_NOISE_SCALE])
+            };
+            let noise = (noise + 1.0) / 2.0;
+
+            let ocean = water_distances.get(&tile_pos).map_or(0.0, |&distance| {
+                (-f64::from(distance - 1) / MOISTURE_OCEAN_DECAY_DISTANCE).exp()
+            });
+
+            // The trade winds and the polar easterlies blow from the east, while the
+            // westerlies blow from the west.
+            let latitude = tile_latitude(&tile_pos, map_size);
+            let westerlies = (30.0..60.0).contains(&(*latitude).abs());
+            let rain_shadow = (1..=RAIN_SHADOW_DISTANCE)
+                .map_while(|distance| {
+                    let x = if map_wrapping.wraps_east_west() {
+                        if westerlies {
+                            (tile_pos.x + map_size.x - distance) % map_size.x
+                        } else {
+                            (tile_pos.x + distance) % map_size.x
+                        }
+                    } else if westerlies {
+                        tile_pos.x.checked_sub(distance)?
+                    } else {
+                        tile_pos
+                            .x
+                            .checked_add(distance)
+                            .filter(|&x| x < map_size.x)?
+                    };
+                    let elevation = elevations[&TilePos { x, y: tile_pos.y }];
+                    // The wind picks up moisture again over water.
+                    (*elevation >= SEA_LEVEL).then_some((distance, elevation))
+                })
+                .filter(|(_, elevation)| *elevation >= mountains_min_elevation)
+                .map(|(distance, _)| {
+                    1.0 - f64::from(distance - 1) / f64::from(RAIN_SHADOW_DISTANCE)
+                })
+                .fold(0.0, f64::max);
+
+            let moisture = (MOISTURE_NOISE_WEIGHT * noise + MOISTURE_OCEAN_WEIGHT * ocean
+                - RAIN_SHADOW_WEIGHT * rain_shadow)
+                .clamp(0.0, 1.0);
+            (tile_pos, NotNan::new(moisture).unwrap())
+        })
+        .collect()
+}
+
+/// Finds the tile positions of all lakes.
+///
+/// A lake is a body of water which is fully enclosed by land (i.e. it does not
+/// touch the edge of the map), and which is no larger than [`LAKE_MAX_SIZE`].
+fn find_lakes<F>(map_size: &TilemapSize, map_wrapping: MapWrapping, is_water: F) -> Vec<TilePos>
+where
+    F: Fn(&TilePos) -> bool,
+{
+    let mut lake_positions = vec![];
+    let mut visited = HashSet::new();
+
+    for x in 0..map_size.x {
+        for y in 0..map_size.y {
+            let tile_pos = TilePos { x, y };
+            if visited.contains(&tile_pos) || !is_water(&tile_pos) {
+                continue;
+            }
+
+            // Flood fill the body of water containing this tile.
+            let mut water_positions = vec![];
+            let mut is_enclosed = true;
+            let mut queue = VecDeque::from([tile_pos]);
+            visited.insert(tile_pos);
+            while let Some(tile_pos) = queue.pop_front() {
+                water_positions.push(tile_pos);
+                let neighbor_positions = neighboring_positions(&tile_pos, map_size, map_wrapping);
+                if neighbor_positions.iter().count() < HEX_DIRECTIONS.len() {
+                    // The body of water extends beyond the edge of the map.
+                    is_enclosed = false;
+                }
+                for &neighbor_pos in neighbor_positions.iter() {
+                    if !visited.contains(&neighbor_pos) && is_water(&neighbor_pos) {
+                        visited.insert(neighbor_pos);
+                        queue.push_back(neighbor_pos);
+                    }
+                }
+            }
+
+            if is_enclosed && water_positions.len() <= LAKE_MAX_SIZE {
+                lake_positions.extend(water_positions);
+            }
+        }
+    }
+
+    lake_positions
+}
+
+/// Finds the tile positions of the ocean which lie on the continental shelf.
+///
+/// The terrain does not model elevation below sea level, so the depth of the
+/// sea is inferred from the relief of the adjacent land instead: low-lying
+/// coasts continue as a wide shelf of shallow water, while highlands drop off
+/// steeply into the deep ocean. See [`CONTINENTAL_SHELF_WIDTHS`].
+fn find_continental_shelf<F, G>(
+    map_size: &TilemapSize,
+    map_wrapping: MapWrapping,
+    is_ocean: F,
+    land_elevation: G,
+) -> Vec<TilePos>
+where
+    F: Fn(&TilePos) -> bool,
+    G: Fn(&TilePos) -> Option<NotNan<f64>>,
+{
+    let mut shelf_positions = vec![];
+    let mut visited = HashSet::new();
+
+    // The relief of each land tile along the shore, i.e. the maximum elevation
+    // of the tile itself and its neighboring land tiles.
+    let mut frontier: BTreeMap<TilePos, NotNan<f64>> = BTreeMap::new();
+    for x in 0..map_size.x {
+        for y in 0..map_size.y {
+            let tile_pos = TilePos { x, y };
+            let Some(elevation) = land_elevation(&tile_pos) else {
+                continue;
+            };
+            visited.insert(tile_pos);
+            let neighbor_positions = neighboring_positions(&tile_pos, map_size, map_wrapping);
+            if !neighbor_positions.iter().any(&is_ocean) {
+                continue;
+            }
+            let relief = neighbor_positions
+                .iter()
+                .filter_map(&land_elevation)
+                .fold(elevation, NotNan::max);
+            frontier.insert(tile_pos, relief);
+        }
+    }
+
+    // Expand outwards from the shore one band of tiles at a time, carrying the
+    // lowest relief of the shore each tile is reachable from.
+    for distance in 1.. {
+        let mut band: BTreeMap<TilePos, NotNan<f64>> = BTreeMap::new();
+        for (tile_pos, &relief) in &frontier {
+            let neighbor_positions = neighboring_positions(tile_pos, map_size, map_wrapping);
+            for &neighbor_pos in neighbor_positions.iter() {
+                if visited.contains(&neighbor_pos) || !is_ocean(&neighbor_pos) {
+                    continue;
+                }
+                band.entry(neighbor_pos)
+                    .and_modify(|band_relief| *band_relief = (*band_relief).min(relief))
+                    .or_insert(relief);
+            }
+        }
+        visited.extend(band.keys().copied());
+        band.retain(|_, relief| {
+            let (_, width) = CONTINENTAL_SHELF_WIDTHS
+                .into_iter()
+                .find(|&(max_relief, _)| **relief < max_relief)
+                .expect("the last shelf width should have no upper bound");
+            distance <= width
+        });
+        if band.is_empty() {
+            break;
+        }
+        shelf_positions.extend(band.keys().copied());
+        frontier = band;
     }
+
+    shelf_positions
+}
+
+/// Chooses the base terrain for a land tile in the climate zone.
+///
+/// `moisture_rank` is the fraction of land tiles in the same climate zone
+/// which are drier than this tile.
+fn choose_base_terrain(
+    rng: &mut fastrand::Rng,
+    climate_zone: ClimateZone,
+    moisture_rank: f64,
+) -> BaseTerrain {
+    let choices: &[BaseTerrain] = match climate_zone {
+        ClimateZone::Frigid => {
+            return rng.choice(FRIGID_ZONE_TERRAIN_CHOICES).unwrap();
+        },
+        ClimateZone::Temperate => &TEMPERATE_ZONE_TERRAIN_CHOICES,
+        ClimateZone::Subtropics => &SUBTROPICS_TERRAIN_CHOICES,
+        ClimateZone::Tropics => &TROPICS_TERRAIN_CHOICES,
+    };
+    let i = (moisture_rank * choices.len() as f64) as usize;
+    choices[i.min(choices.len() - 1)]
+}
+
+/// Returns the texture of the base terrain, in the order of the images of the
+/// base terrain layer.
+fn base_terrain_texture_index(base_terrain: BaseTerrain) -> TileTextureIndex {
+    TileTextureIndex(match base_terrain {
+        BaseTerrain::Plains => 0,
+        BaseTerrain::Grassland => 1,
+        BaseTerrain::Desert => 2,
+        BaseTerrain::Tundra => 3,
+        BaseTerrain::Snow => 4,
+        BaseTerrain::PlainsHills => 5,
+        BaseTerrain::GrasslandHills => 6,
+        BaseTerrain::DesertHills => 7,
+        BaseTerrain::TundraHills => 8,
+        BaseTerrain::SnowHills => 9,
+        BaseTerrain::PlainsMountains => 10,
+        BaseTerrain::GrasslandMountains => 11,
+        BaseTerrain::DesertMountains => 12,
+        BaseTerrain::TundraMountains => 13,
+        BaseTerrain::SnowMountains => 14,
+        BaseTerrain::Coast => 15,
+        BaseTerrain::Ocean => 16,
+        BaseTerrain::Lake => 17,
+    })
+}
+
+/// Returns the texture of the terrain features, in the order of the images of
+/// the terrain features layer.
+fn terrain_features_texture_index(terrain_features: TerrainFeatures) -> TileTextureIndex {
+    TileTextureIndex(match terrain_features {
+        TerrainFeatures::Woods => 0,
+        TerrainFeatures::Rainforest => 1,
+        TerrainFeatures::Marsh => 2,
+        TerrainFeatures::Floodplains => 3,
+        TerrainFeatures::Oasis => 4,
+        TerrainFeatures::Cliffs => 5,
+        TerrainFeatures::Ice => 6,
+    })
 }
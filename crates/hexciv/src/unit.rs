@@ -1,16 +1,12 @@
 use std::any::TypeId;
-use std::cell::RefCell;
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::iter;
+use std::collections::{HashMap, VecDeque};
 use std::ops::Add;
 
 use bevy::ecs::query::QueryFilter;
 use bevy::prelude::*;
 use bevy::render::sync_world::SyncToRenderWorld;
-use bevy_ecs_tilemap::helpers::hex_grid::cube::CubePos;
-use bevy_ecs_tilemap::helpers::hex_grid::neighbors::{HEX_DIRECTIONS, HexNeighbors};
+use bevy_ecs_tilemap::helpers::hex_grid::neighbors::HEX_DIRECTIONS;
 use bevy_ecs_tilemap::prelude::*;
-use bitvec::prelude::*;
 use bon::bon;
 use derive_more::Display;
 use indexmap::IndexSet;
@@ -23,21 +19,42 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::action::GlobalAction;
+use crate::asset::FontHandle;
 use crate::civilization::Civilization;
-use crate::game_setup::GameRng;
+use crate::fog_of_war::{PlayerVisibility, UNIT_SIGHT_RANGE, positions_in_sight_range};
+use crate::game_setup::{GameRng, MapWrapping};
 use crate::input::CursorTilePos;
 use crate::layer::{
-    BaseTerrainLayer, BaseTerrainLayerFilter, CivilianUnitLayer, CivilianUnitLayerFilter,
-    LandMilitaryUnitLayer, LandMilitaryUnitLayerFilter, LayerZIndex as _, RiverLayer,
-    RiverLayerFilter, TerrainFeaturesLayer, TerrainFeaturesLayerFilter, UnitLayersFilter,
+    BaseTerrainLayer, CivilianUnitLayer, CivilianUnitLayerFilter, CliffLayer, FogOfWarLayer,
+    LandMilitaryUnitLayer, LandMilitaryUnitLayerFilter, LayerZIndex as _, MovementPathLayer,
+    MovementPathLayerFilter, ResourceLayer, RiverLayer, TerrainFeaturesLayer, UnitLayersFilter,
     UnitSelectionLayer, UnitSelectionLayerFilter, UnitStateLayer, UnitStateLayerFilter,
 };
+use crate::map::{GameMap, Tile};
 use crate::peer::{HostBroadcast, Request};
-use crate::player::{OurPlayer, Player};
+use crate::player::{OurPlayer, Player, PlayerCapabilities};
 use crate::state::{MultiplayerState, TurnState};
-use crate::terrain::{BaseTerrain, RiverHexEdges, TerrainFeatures};
+use crate::terrain::{
+    StartingPositions, TerrainFeatures, WrapAroundCamera, hex_distance, neighboring_positions,
+};
 use crate::turn::TurnStarted;
 
+/// The movement cost assumed for the tiles which the player has never seen.
+const UNEXPLORED_MOVEMENT_COST: u32 = 1;
+
+/// The full movement points of embarked units, regardless of the unit type.
+const EMBARKED_FULL_MOVEMENT_POINTS: u32 = 3;
+
+/// The movement cost for embarked units to move to another water tile.
+const EMBARKED_MOVEMENT_COST: u32 = 1;
+
+/// The movement cost assumed in pathfinding for embarking / disembarking, which
+/// actually costs all the remaining movement points.
+const EMBARKATION_MOVEMENT_COST: u32 = 2;
+
+/// How far the turn labels are in front of the movement path markers.
+const MOVEMENT_PATH_LABEL_Z_OFFSET: f32 = 0.1;
+
 /// A map from [`UnitId`] to [`Entity`] ID.
 #[derive(Default, Resource)]
 pub struct UnitEntityMap(pub HashMap<UnitId, Entity>);
@@ -105,6 +122,13 @@ pub struct MovementPoints(pub NotNan<f64>);
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct FullMovementPoints(pub NotNan<f64>);
 
+/// An order for the unit to move to the goal, continuing over the next turns
+/// until it arrives.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Component)]
+pub struct MoveOrder {
+    pub goal: TilePos,
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Default, Component, IntoPrimitive, TryFromPrimitive)]
 #[repr(u32)]
 pub enum UnitState {
@@ -117,6 +141,9 @@ pub enum UnitState {
     LandMilitaryFortifiedOutOfOrders = 5,
     CivilianOutOfMoves = 6,
     LandMilitaryOutOfMoves = 7,
+    EmbarkedReady = 8,
+    EmbarkedReadyOutOfOrders = 9,
+    EmbarkedOutOfMoves = 10,
 }
 
 #[derive(Copy, Clone, IntoPrimitive)]
@@ -149,6 +176,34 @@ pub enum UnitSelection {
     Active = 0,
 }
 
+/// The markers shown along the path previewed for moving the active unit.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, IntoPrimitive, TryFromPrimitive)]
+#[repr(u32)]
+pub enum MovementPathMarker {
+    /// A tile which the unit passes through.
+    Step = 0,
+    /// The tile where the unit runs out of movement points for the turn, or
+    /// arrives at the goal.
+    TurnEnd = 1,
+    /// The goal which the unit has no known path to.
+    NoPath = 2,
+}
+
+/// A label showing the number of turns for the unit to reach the tile at a
+/// [`MovementPathMarker::TurnEnd`].
+#[derive(Component)]
+pub struct MovementPathLabel;
+
+/// The movement path currently previewed, to avoid finding the same path again
+/// every frame.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct MovementPathPreview {
+    unit_entity: Entity,
+    start: TilePos,
+    goal: TilePos,
+    movement_points: MovementPoints,
+}
+
 #[derive(Component)]
 pub struct ActionsLegend;
 
@@ -171,8 +226,12 @@ pub struct UnitFilter(
     With<Unit>,
     Without<BaseTerrainLayer>,
     Without<RiverLayer>,
+    Without<CliffLayer>,
     Without<TerrainFeaturesLayer>,
+    Without<ResourceLayer>,
+    Without<FogOfWarLayer>,
     Without<UnitSelectionLayer>,
+    Without<MovementPathLayer>,
     Without<UnitStateLayer>,
     Without<CivilianUnitLayer>,
     Without<LandMilitaryUnitLayer>,
@@ -242,6 +301,27 @@ impl From<LandMilitaryUnitType> for FullMovementPoints {
     }
 }
 
+impl Unit {
+    /// Returns the full movement points of the unit, which are the same for all
+    /// unit types while embarked.
+    pub fn full_movement_points_for(&self, is_embarked: bool) -> FullMovementPoints {
+        if is_embarked {
+            FullMovementPoints(EMBARKED_FULL_MOVEMENT_POINTS.into())
+        } else {
+            self.full_movement_points
+        }
+    }
+}
+
+impl UnitState {
+    pub fn is_embarked(&self) -> bool {
+        matches!(
+            self,
+            Self::EmbarkedReady | Self::EmbarkedReadyOutOfOrders | Self::EmbarkedOutOfMoves
+        )
+    }
+}
+
 impl From<FullMovementPoints> for MovementPoints {
     fn from(full_movement_points: FullMovementPoints) -> Self {
         Self(full_movement_points.0)
@@ -258,12 +338,16 @@ impl Add<UnitStateModifier> for UnitState {
                 let modifier: u32 = rhs.into();
                 Self::try_from(state + modifier).unwrap()
             },
+            Self::EmbarkedReady => match rhs {
+                UnitStateModifier::OutOfOrders => Self::EmbarkedReadyOutOfOrders,
+            },
             Self::CivilianReadyOutOfOrders
             | Self::LandMilitaryReadyOutOfOrders
-            | Self::LandMilitaryFortifiedOutOfOrders => {
+            | Self::LandMilitaryFortifiedOutOfOrders
+            | Self::EmbarkedReadyOutOfOrders => {
                 unimplemented!("unit state modifiers are not stackable");
             },
-            Self::CivilianOutOfMoves | Self::LandMilitaryOutOfMoves => {
+            Self::CivilianOutOfMoves | Self::LandMilitaryOutOfMoves | Self::EmbarkedOutOfMoves => {
                 unimplemented!("out-of-moves unit states do not have modifiers");
             },
         }
@@ -317,69 +401,39 @@ impl UnitStateTileBundle {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_starting_units(
     mut game_rng: ResMut<GameRng>,
+    starting_positions: Res<StartingPositions>,
+    map_wrapping: Res<MapWrapping>,
+    game_map: Res<GameMap>,
     player_query: Query<(&Player,), With<Player>>,
-    base_terrain_tilemap_query: Single<(&TilemapSize, &TileStorage), BaseTerrainLayerFilter>,
-    base_terrain_tile_query: Query<(&TileTextureIndex,), BaseTerrainLayerFilter>,
     mut turn_started_events: EventWriter<TurnStarted>,
     mut unit_spawned_events: EventWriter<UnitSpawned>,
 ) {
     let rng = &mut game_rng.0;
+    let map_size = &game_map.size();
 
-    let (map_size, base_terrain_tile_storage) = base_terrain_tilemap_query.into_inner();
-
-    let mut allowable_starting_positions = HashSet::new();
-    for x in 0..map_size.x {
-        for y in 0..map_size.y {
-            let tile_pos = TilePos { x, y };
-            let base_terrain_tile_entity = base_terrain_tile_storage.get(&tile_pos).unwrap();
-            let (base_terrain_tile_texture,) = base_terrain_tile_query
-                .get(base_terrain_tile_entity)
-                .unwrap();
-
-            if [BaseTerrain::Ocean, BaseTerrain::Coast]
-                .into_iter()
-                .chain(BaseTerrain::MOUNTAINS)
-                .map(u32::from)
-                .contains(&base_terrain_tile_texture.0)
-            {
-                continue;
-            }
-
-            allowable_starting_positions.insert(tile_pos);
-        }
-    }
+    let is_allowable = |tile_pos: &TilePos| {
+        let base_terrain = game_map[tile_pos].base_terrain;
+        !base_terrain.is_water() && !base_terrain.is_mountains()
+    };
 
-    for (&Player { civ, .. },) in player_query.iter() {
-        // TODO: Space out the starting positions for different civs.
-        let (settler_tile_pos, warrior_tile_pos) = {
-            let rng = RefCell::new(&mut *rng);
-            iter::from_fn({
-                let rng = &rng;
-                let mut allowable_starting_positions = allowable_starting_positions.clone();
-                move || {
-                    let settler_tile_pos =
-                        *rng.borrow_mut().choice(&allowable_starting_positions)?;
-                    allowable_starting_positions.remove(&settler_tile_pos);
-                    Some(settler_tile_pos)
-                }
-            })
-            .find_map(|settler_tile_pos| {
-                let neighbor_positions: HashSet<_> =
-                    HexNeighbors::get_neighboring_positions_row_odd(&settler_tile_pos, map_size)
-                        .iter()
-                        .copied()
-                        .collect();
-                let allowable_neighbor_positions: HashSet<_> = neighbor_positions
-                    .intersection(&allowable_starting_positions)
+    for (&Player { civ, .. }, &settler_tile_pos) in player_query
+        .iter()
+        .map(|(player,)| player)
+        .sorted_by_key(|player| player.player_index)
+        .zip(&starting_positions.0)
+    {
+        let warrior_tile_pos = {
+            let allowable_neighbor_positions: Vec<_> =
+                neighboring_positions(&settler_tile_pos, map_size, *map_wrapping)
+                    .iter()
+                    .filter(|neighbor_pos| is_allowable(neighbor_pos))
                     .copied()
                     .collect();
-                rng.borrow_mut()
-                    .choice(allowable_neighbor_positions)
-                    .map(|warrior_tile_pos| (settler_tile_pos, warrior_tile_pos))
-            })
-            .expect("the map should have enough land tiles to spawn starting units")
+            *rng.choice(&allowable_neighbor_positions)
+                .expect("the starting position should have enough land tiles around it")
         };
 
         // Spawn settler.
@@ -402,12 +456,37 @@ pub fn spawn_starting_units(
     turn_started_events.write(TurnStarted { turn_num: 1 });
 }
 
-/// Resets movement points for all units.
-pub fn reset_movement_points(mut unit_query: Query<(&Unit, &mut MovementPoints), UnitFilter>) {
-    for (unit, mut movement_points) in unit_query.iter_mut() {
-        movement_points.set_if_neq(MovementPoints::from(unit.full_movement_points));
+/// Resets the movement points of all units for the new turn, and makes the
+/// units which were out of moves or skipped the last turn ready again.
+pub fn reset_movement_points(
+    mut unit_query: Query<(&Unit, &mut MovementPoints, &mut UnitState), UnitFilter>,
+    mut unit_state_tile_query: Query<(&UnitEntityId, &mut TileTextureIndex), UnitStateLayerFilter>,
+) {
+    for (unit, mut movement_points, mut unit_state) in unit_query.iter_mut() {
+        movement_points.set_if_neq(MovementPoints::from(
+            unit.full_movement_points_for(unit_state.is_embarked()),
+        ));
+
+        let next_unit_state = match *unit_state {
+            UnitState::CivilianReadyOutOfOrders | UnitState::CivilianOutOfMoves => {
+                UnitState::CivilianReady
+            },
+            UnitState::LandMilitaryReadyOutOfOrders | UnitState::LandMilitaryOutOfMoves => {
+                UnitState::LandMilitaryReady
+            },
+            UnitState::EmbarkedReadyOutOfOrders | UnitState::EmbarkedOutOfMoves => {
+                UnitState::EmbarkedReady
+            },
+            unit_state => unit_state,
+        };
+        unit_state.set_if_neq(next_unit_state);
+    }
 
-        // TODO: Update `UnitState`.
+    // Update the unit state tiles showing the units whose unit states have been
+    // reset.
+    for (&UnitEntityId(unit_entity), mut tile_texture) in unit_state_tile_query.iter_mut() {
+        let (_unit, _movement_points, unit_state) = unit_query.get(unit_entity).unwrap();
+        tile_texture.set_if_neq(TileTextureIndex((*unit_state).into()));
     }
 }
 
@@ -415,24 +494,30 @@ pub fn reset_movement_points(mut unit_query: Query<(&Unit, &mut MovementPoints),
 pub fn has_ready_units(
     our_player: Res<OurPlayer>,
     player_query: Query<(&Player,), With<Player>>,
-    unit_query: Query<(Entity, &Unit, &TilePos, &UnitState), UnitFilter>,
+    unit_query: Query<(Entity, &Unit, &TilePos, &UnitState, Has<MoveOrder>), UnitFilter>,
 ) -> bool {
     let (Player {
         civ: current_civ, ..
     },) = player_query.get(our_player.0).unwrap();
 
-    unit_query
-        .iter()
-        .any(|(_unit_entity, Unit { civ, .. }, _tile_pos, unit_state)| {
+    unit_query.iter().any(
+        |(_unit_entity, Unit { civ, .. }, _tile_pos, unit_state, has_move_order)| {
             civ == current_civ
                 && matches!(
                     unit_state,
-                    UnitState::CivilianReady | UnitState::LandMilitaryReady
+                    UnitState::CivilianReady
+                        | UnitState::LandMilitaryReady
+                        | UnitState::EmbarkedReady
                 )
-        })
+                && !has_move_order
+        },
+    )
 }
 
 /// Cycles to the previous / next ready unit controlled by the current player.
+///
+/// The units with move orders are skipped, as they already have something to
+/// do.
 pub fn cycle_ready_unit(
     global_action_state: Res<ActionState<GlobalAction>>,
     our_player: Res<OurPlayer>,
@@ -441,7 +526,7 @@ pub fn cycle_ready_unit(
         (&TilePos, &TileTextureIndex, &UnitEntityId),
         UnitSelectionLayerFilter,
     >,
-    unit_query: Query<(Entity, &Unit, &TilePos, &UnitState), UnitFilter>,
+    unit_query: Query<(Entity, &Unit, &TilePos, &UnitState, Has<MoveOrder>), UnitFilter>,
     mut unit_selected_events: EventWriter<UnitSelected>,
 ) {
     let (Player {
@@ -451,18 +536,23 @@ pub fn cycle_ready_unit(
     let ready_units: IndexSet<_> = unit_query
         .iter()
         .sorted_unstable_by_key(|(_unit_entity, unit, ..)| unit.unit_id)
-        .filter_map(|(unit_entity, Unit { civ, .. }, tile_pos, unit_state)| {
-            if civ == current_civ
-                && matches!(
-                    unit_state,
-                    UnitState::CivilianReady | UnitState::LandMilitaryReady
-                )
-            {
-                Some((unit_entity, *tile_pos))
-            } else {
-                None
-            }
-        })
+        .filter_map(
+            |(unit_entity, Unit { civ, .. }, tile_pos, unit_state, has_move_order)| {
+                if civ == current_civ
+                    && matches!(
+                        unit_state,
+                        UnitState::CivilianReady
+                            | UnitState::LandMilitaryReady
+                            | UnitState::EmbarkedReady
+                    )
+                    && !has_move_order
+                {
+                    Some((unit_entity, *tile_pos))
+                } else {
+                    None
+                }
+            },
+        )
         .collect();
     if ready_units.is_empty() {
         // There are no ready units to cycle to.
@@ -483,13 +573,15 @@ pub fn cycle_ready_unit(
         let units: Vec<_> = unit_query
             .iter()
             .sorted_unstable_by_key(|(_unit_entity, unit, ..)| unit.unit_id)
-            .filter_map(|(unit_entity, Unit { civ, .. }, &tile_pos, _unit_state)| {
-                if civ == current_civ {
-                    Some((unit_entity, tile_pos))
-                } else {
-                    None
-                }
-            })
+            .filter_map(
+                |(unit_entity, Unit { civ, .. }, &tile_pos, _unit_state, _has_move_order)| {
+                    if civ == current_civ {
+                        Some((unit_entity, tile_pos))
+                    } else {
+                        None
+                    }
+                },
+            )
             .collect();
 
         if global_action_state.just_pressed(&GlobalAction::PreviousReadyUnit) {
@@ -535,7 +627,14 @@ pub fn cycle_ready_unit(
 
 #[allow(clippy::type_complexity)]
 pub fn focus_camera_on_active_unit(
-    camera_query: Single<(&mut Transform,), (With<Camera2d>, Without<UnitSelectionLayer>)>,
+    camera_query: Single<
+        (&mut Transform,),
+        (
+            With<Camera2d>,
+            Without<WrapAroundCamera>,
+            Without<UnitSelectionLayer>,
+        ),
+    >,
     unit_selection_tilemap_query: Single<
         (
             &Transform,
@@ -569,6 +668,7 @@ pub fn focus_camera_on_active_unit(
 }
 
 pub fn mark_active_unit_out_of_orders(
+    mut commands: Commands,
     unit_state_tilemap_query: Single<(&TileStorage,), UnitStateLayerFilter>,
     unit_selection_tile_query: Query<
         (&TilePos, &TileTextureIndex, &UnitEntityId),
@@ -592,6 +692,9 @@ pub fn mark_active_unit_out_of_orders(
         return;
     };
 
+    // The new order cancels any move order which the unit was given before.
+    commands.entity(active_unit_entity).remove::<MoveOrder>();
+
     let (mut unit_state,) = unit_query.get_mut(active_unit_entity).unwrap();
     let next_unit_state = match *unit_state {
         UnitState::CivilianReady => UnitState::CivilianReady + UnitStateModifier::OutOfOrders,
@@ -604,6 +707,7 @@ pub fn mark_active_unit_out_of_orders(
         s if s == UnitState::LandMilitaryFortified + UnitStateModifier::OutOfOrders => {
             UnitState::LandMilitaryReady + UnitStateModifier::OutOfOrders
         },
+        UnitState::EmbarkedReady => UnitState::EmbarkedReady + UnitStateModifier::OutOfOrders,
         _ => {
             // Unit state is unchanged.
             return;
@@ -619,6 +723,7 @@ pub fn mark_active_unit_out_of_orders(
 }
 
 pub fn mark_active_unit_fortified(
+    mut commands: Commands,
     unit_state_tilemap_query: Single<(&TileStorage,), UnitStateLayerFilter>,
     land_military_unit_tilemap_query: Single<(&TileStorage,), LandMilitaryUnitLayerFilter>,
     unit_selection_tile_query: Query<
@@ -653,6 +758,14 @@ pub fn mark_active_unit_fortified(
     }
 
     let (mut unit_state,) = unit_query.get_mut(active_unit_entity).unwrap();
+    if unit_state.is_embarked() {
+        // Embarked units can't fortify.
+        return;
+    }
+
+    // The new order cancels any move order which the unit was given before.
+    commands.entity(active_unit_entity).remove::<MoveOrder>();
+
     let next_unit_state = UnitState::LandMilitaryFortified + UnitStateModifier::OutOfOrders;
     unit_state.set_if_neq(next_unit_state);
 
@@ -775,25 +888,22 @@ pub fn should_move_active_unit_to(
 
 #[allow(clippy::too_many_arguments)]
 pub fn move_active_unit_to(
+    mut commands: Commands,
     cursor_tile_pos: Res<CursorTilePos>,
+    map_wrapping: Res<MapWrapping>,
     multiplayer_state: Res<State<MultiplayerState>>,
-    base_terrain_tilemap_query: Single<(&TilemapSize, &TileStorage), BaseTerrainLayerFilter>,
-    river_tilemap_query: Single<(&TileStorage,), RiverLayerFilter>,
-    terrain_features_tilemap_query: Single<(&TileStorage,), TerrainFeaturesLayerFilter>,
+    game_map: Res<GameMap>,
+    our_player: Res<OurPlayer>,
+    player_query: Query<(&PlayerVisibility, &PlayerCapabilities), With<Player>>,
     unit_state_tilemap_query: Single<(&TileStorage,), UnitStateLayerFilter>,
-    base_terrain_tile_query: Query<(&TileTextureIndex,), BaseTerrainLayerFilter>,
-    river_tile_query: Query<(&TileTextureIndex,), RiverLayerFilter>,
-    terrain_features_tile_query: Query<(&TileTextureIndex,), TerrainFeaturesLayerFilter>,
     unit_selection_tile_query: Query<(&TilePos, &TileTextureIndex), UnitSelectionLayerFilter>,
     unit_state_tile_query: Query<(&UnitEntityId,), UnitStateLayerFilter>,
     unit_query: Query<(&Unit, &MovementPoints), UnitFilter>,
     mut request_events: EventWriter<Request>,
     mut unit_moved_events: EventWriter<UnitMoved>,
 ) {
-    let (map_size, base_terrain_tile_storage) = base_terrain_tilemap_query.into_inner();
-    let (river_tile_storage,) = river_tilemap_query.into_inner();
-    let (terrain_features_tile_storage,) = terrain_features_tilemap_query.into_inner();
     let (unit_state_tile_storage,) = unit_state_tilemap_query.into_inner();
+    let (player_visibility, player_capabilities) = player_query.get(our_player.0).unwrap();
 
     let active_unit_selection_pos = unit_selection_tile_query
         .iter()
@@ -812,162 +922,589 @@ pub fn move_active_unit_to(
         .get(&start)
         .map(|tile_entity| unit_state_tile_query.get(tile_entity).unwrap())
         .expect("active unit tile position should have unit state tile");
+    let (unit, movement_points) = unit_query.get(unit_entity).unwrap();
+
+    let mut known_terrain = KnownTerrain {
+        player_visibility,
+        can_embark: player_capabilities.can_embark,
+        revealed_tiles: HashMap::new(),
+    };
+    let (unit_moves, outcome) = known_terrain.plan_moves(
+        &game_map,
+        *map_wrapping,
+        unit,
+        start,
+        goal,
+        *movement_points,
+    );
+    for unit_moved in unit_moves {
+        send_unit_moved(
+            &multiplayer_state,
+            unit_moved,
+            &mut request_events,
+            &mut unit_moved_events,
+        );
+    }
+
+    // The new order replaces any move order which the unit was given before.
+    match outcome {
+        MoveOutcome::OutOfMovementPoints => {
+            commands.entity(unit_entity).insert(MoveOrder { goal });
+        },
+        MoveOutcome::Arrived | MoveOutcome::NoPath => {
+            commands.entity(unit_entity).remove::<MoveOrder>();
+        },
+    }
+}
+
+/// Moves the units controlled by the current player towards the goals of their
+/// move orders.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_move_orders(
+    mut commands: Commands,
+    map_wrapping: Res<MapWrapping>,
+    multiplayer_state: Res<State<MultiplayerState>>,
+    game_map: Res<GameMap>,
+    our_player: Res<OurPlayer>,
+    player_query: Query<(&Player, &PlayerVisibility, &PlayerCapabilities), With<Player>>,
+    unit_query: Query<(Entity, &Unit, &TilePos, &MovementPoints, &MoveOrder), UnitFilter>,
+    mut request_events: EventWriter<Request>,
+    mut unit_moved_events: EventWriter<UnitMoved>,
+) {
     let (
-        &Unit {
-            unit_id,
-            full_movement_points,
-            ..
+        Player {
+            civ: current_civ, ..
         },
-        movement_points,
-    ) = unit_query.get(unit_entity).unwrap();
+        player_visibility,
+        player_capabilities,
+    ) = player_query.get(our_player.0).unwrap();
 
-    let successors = |(x, y)| {
-        let tile_pos = TilePos { x, y };
-        let neighbor_positions =
-            HexNeighbors::get_neighboring_positions_row_odd(&tile_pos, map_size);
-        let neighbor_positions_map: BTreeMap<_, _> = HEX_DIRECTIONS
-            .into_iter()
-            .filter_map(move |direction| {
-                neighbor_positions
-                    .get(direction)
-                    .map(|tile_pos| (direction, *tile_pos))
-            })
-            .collect();
-        let river_hex_edges: RiverHexEdges = river_tile_storage
-            .get(&tile_pos)
-            .map(|tile_entity| river_tile_query.get(tile_entity).unwrap())
-            .map_or(BitArray::<_>::ZERO, |(tile_texture,)| {
-                let mut river_hex_edges: RiverHexEdges = BitArray::<_>::ZERO;
-                river_hex_edges.store(tile_texture.0);
-                river_hex_edges
+    for (unit_entity, unit, &tile_pos, movement_points, &MoveOrder { goal }) in unit_query
+        .iter()
+        .filter(|(_unit_entity, unit, ..)| unit.civ == *current_civ)
+        .sorted_unstable_by_key(|(_unit_entity, unit, ..)| unit.unit_id)
+    {
+        // The path is planned again every turn, as the known terrain might have
+        // changed since the order was given.
+        let mut known_terrain = KnownTerrain {
+            player_visibility,
+            can_embark: player_capabilities.can_embark,
+            revealed_tiles: HashMap::new(),
+        };
+        let (unit_moves, outcome) = known_terrain.plan_moves(
+            &game_map,
+            *map_wrapping,
+            unit,
+            tile_pos,
+            goal,
+            *movement_points,
+        );
+        for unit_moved in unit_moves {
+            send_unit_moved(
+                &multiplayer_state,
+                unit_moved,
+                &mut request_events,
+                &mut unit_moved_events,
+            );
+        }
+
+        match outcome {
+            MoveOutcome::OutOfMovementPoints => {},
+            MoveOutcome::Arrived => {
+                commands.entity(unit_entity).remove::<MoveOrder>();
+            },
+            MoveOutcome::NoPath => {
+                info!(unit_id = ?unit.unit_id, ?goal, "cancelled move order without path");
+                commands.entity(unit_entity).remove::<MoveOrder>();
+            },
+        }
+    }
+}
+
+/// Previews the path for moving the active unit to the cursor's tile position,
+/// with markers where the unit would run out of movement points in each turn.
+///
+/// If there is no known path to the tile, the tile is marked as unreachable
+/// instead.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_movement_path_preview(
+    mut commands: Commands,
+    font_handle: Res<FontHandle>,
+    cursor_tile_pos: Option<Res<CursorTilePos>>,
+    map_wrapping: Res<MapWrapping>,
+    our_player: Res<OurPlayer>,
+    player_query: Query<(Ref<PlayerVisibility>, &PlayerCapabilities), With<Player>>,
+    movement_path_tilemap_query: Single<
+        (
+            Entity,
+            &mut TileStorage,
+            &Transform,
+            &TilemapType,
+            &TilemapSize,
+            &TilemapAnchor,
+            &TilemapGridSize,
+            &TilemapTileSize,
+        ),
+        MovementPathLayerFilter,
+    >,
+    movement_path_tile_query: Query<(Entity, &TilePos), MovementPathLayerFilter>,
+    movement_path_label_query: Query<(Entity,), With<MovementPathLabel>>,
+    unit_selection_tile_query: Query<
+        (&TilePos, &TileTextureIndex, &UnitEntityId),
+        UnitSelectionLayerFilter,
+    >,
+    unit_query: Query<(&Unit, &MovementPoints), UnitFilter>,
+    mut last_preview: Local<Option<MovementPathPreview>>,
+) {
+    let (
+        movement_path_tilemap_entity,
+        mut movement_path_tile_storage,
+        map_transform,
+        map_type,
+        map_size,
+        map_anchor,
+        grid_size,
+        tile_size,
+    ) = movement_path_tilemap_query.into_inner();
+    let (player_visibility, player_capabilities) = player_query.get(our_player.0).unwrap();
+
+    let active_unit_selection =
+        unit_selection_tile_query
+            .iter()
+            .find(|&(_tile_pos, &tile_texture, _unit_entity_id)| {
+                matches!(tile_texture, TileTextureIndex(t) if t == u32::from(UnitSelection::Active))
             });
+    let preview = match (active_unit_selection, cursor_tile_pos) {
+        (Some((&start, _tile_texture, &UnitEntityId(unit_entity))), Some(cursor_tile_pos))
+            if cursor_tile_pos.0 != start =>
+        {
+            let (_unit, &movement_points) = unit_query.get(unit_entity).unwrap();
+            Some(MovementPathPreview {
+                unit_entity,
+                start,
+                goal: cursor_tile_pos.0,
+                movement_points,
+            })
+        },
+        _ => None,
+    };
+    if preview == *last_preview && !player_visibility.is_changed() {
+        // The previewed path is still up to date.
+        return;
+    }
+    *last_preview = preview;
 
-        neighbor_positions_map.into_iter().filter_map({
-            #[allow(clippy::borrow_deref_ref)]
-            let base_terrain_tile_storage = &*base_terrain_tile_storage;
-            #[allow(clippy::borrow_deref_ref)]
-            let terrain_features_tile_storage = &*terrain_features_tile_storage;
-            let base_terrain_tile_query = base_terrain_tile_query.as_readonly();
-            let terrain_features_tile_query = terrain_features_tile_query.as_readonly();
-            move |(direction, tile_pos)| {
-                let (base_terrain_tile_texture,) = {
-                    let tile_entity = base_terrain_tile_storage.get(&tile_pos).unwrap();
-                    base_terrain_tile_query.get(tile_entity).unwrap()
-                };
-                let base_terrain = BaseTerrain::try_from(base_terrain_tile_texture.0).unwrap();
-                if base_terrain.is_mountains() {
-                    return None;
-                }
-                // TODO: Conditionally allow units to embark.
-                if [BaseTerrain::Ocean, BaseTerrain::Coast].contains(&base_terrain) {
-                    return None;
-                }
-                let terrain_features_tile_texture = terrain_features_tile_storage
-                    .get(&tile_pos)
-                    .map(|tile_entity| terrain_features_tile_query.get(tile_entity).unwrap())
-                    .map(|(tile_texture,)| *tile_texture);
-                let movement_cost = if base_terrain.is_hills() {
-                    match terrain_features_tile_texture {
-                        Some(TileTextureIndex(t)) if t == u32::from(TerrainFeatures::Woods) => {
-                            NotNan::from(3)
-                        },
-                        Some(TileTextureIndex(t))
-                            if t == u32::from(TerrainFeatures::Rainforest) =>
-                        {
-                            NotNan::from(3)
-                        },
-                        _ => NotNan::from(2),
-                    }
-                } else {
-                    match terrain_features_tile_texture {
-                        Some(TileTextureIndex(t)) if t == u32::from(TerrainFeatures::Woods) => {
-                            NotNan::from(2)
-                        },
-                        Some(TileTextureIndex(t))
-                            if t == u32::from(TerrainFeatures::Rainforest) =>
-                        {
-                            NotNan::from(2)
-                        },
-                        Some(TileTextureIndex(t)) if t == u32::from(TerrainFeatures::Marsh) => {
-                            NotNan::from(2)
-                        },
-                        _ => NotNan::from(1),
-                    }
-                };
-                let movement_cost = if river_hex_edges[direction as usize] {
-                    movement_cost + NotNan::from(3)
-                } else {
-                    movement_cost
-                };
+    // Clear the previously previewed path.
+    for (tile_entity, tile_pos) in movement_path_tile_query.iter() {
+        movement_path_tile_storage.remove(tile_pos);
+        commands.entity(tile_entity).despawn();
+    }
+    for (label_entity,) in movement_path_label_query.iter() {
+        commands.entity(label_entity).despawn();
+    }
 
-                let TilePos { x, y } = tile_pos;
-                Some(((x, y), movement_cost))
+    let Some(MovementPathPreview {
+        unit_entity,
+        start,
+        goal,
+        movement_points,
+    }) = preview
+    else {
+        // Nothing to preview.
+        return;
+    };
+    let (unit, _movement_points) = unit_query.get(unit_entity).unwrap();
+
+    let known_terrain = KnownTerrain {
+        player_visibility: &player_visibility,
+        can_embark: player_capabilities.can_embark,
+        revealed_tiles: HashMap::new(),
+    };
+    let mut markers = Vec::new();
+    if let Some(path) = known_terrain.find_path(&start, &goal, map_size, *map_wrapping) {
+        let mut current = start;
+        let mut movement_points = movement_points;
+        let mut turn = 1;
+        let mut steps = path.into_iter().peekable();
+        while let Some((next, movement_cost)) = steps.next() {
+            if known_terrain
+                .spend_movement_points(unit, &current, &next, movement_cost, &mut movement_points)
+                .is_none()
+            {
+                // The unit would continue moving in the next turn, with full movement points.
+                turn += 1;
+                movement_points = MovementPoints::from(
+                    unit.full_movement_points_for(known_terrain.is_water(&current)),
+                );
+                known_terrain.spend_movement_points(
+                    unit,
+                    &current,
+                    &next,
+                    movement_cost,
+                    &mut movement_points,
+                );
             }
-        })
+            let is_turn_end = match steps.peek() {
+                Some(&(next_next, next_movement_cost)) => {
+                    let mut next_movement_points = movement_points;
+                    known_terrain
+                        .spend_movement_points(
+                            unit,
+                            &next,
+                            &next_next,
+                            next_movement_cost,
+                            &mut next_movement_points,
+                        )
+                        .is_none()
+                },
+                None => true,
+            };
+            if is_turn_end {
+                markers.push((next, MovementPathMarker::TurnEnd, Some(turn)));
+            } else {
+                markers.push((next, MovementPathMarker::Step, None));
+            }
+            current = next;
+        }
+    } else {
+        markers.push((goal, MovementPathMarker::NoPath, None));
+    }
+
+    let text_font = TextFont {
+        font: font_handle.0.clone(),
+        font_size: 20.0,
+        ..Default::default()
     };
+    for (tile_pos, marker, turn) in markers {
+        let tile_entity = commands
+            .spawn(TileBundle {
+                position: tile_pos,
+                tilemap_id: TilemapId(movement_path_tilemap_entity),
+                texture_index: TileTextureIndex(marker.into()),
+                ..Default::default()
+            })
+            .insert(MovementPathLayer)
+            .id();
+        movement_path_tile_storage.set(&tile_pos, tile_entity);
+
+        if let Some(turn) = turn {
+            let tile_center = tile_pos
+                .center_in_world(map_size, grid_size, tile_size, map_type, map_anchor)
+                .extend(MOVEMENT_PATH_LABEL_Z_OFFSET);
+            commands.spawn((
+                Text2d::new(turn.to_string()),
+                text_font.clone(),
+                TextColor(Color::BLACK),
+                TextLayout {
+                    justify: JustifyText::Center,
+                    ..Default::default()
+                },
+                *map_transform * Transform::from_translation(tile_center),
+                MovementPathLabel,
+            ));
+        }
+    }
+}
+
+/// Sends the unit move to the host, or handles it if we are the host.
+fn send_unit_moved(
+    multiplayer_state: &State<MultiplayerState>,
+    unit_moved: UnitMoved,
+    request_events: &mut EventWriter<Request>,
+    unit_moved_events: &mut EventWriter<UnitMoved>,
+) {
+    match multiplayer_state.get() {
+        MultiplayerState::Hosting => {
+            unit_moved_events.write(unit_moved);
+        },
+        MultiplayerState::Joining => {
+            request_events.write(unit_moved.into());
+        },
+        _ => {
+            unreachable!("multiplayer state should not be inactive");
+        },
+    }
+}
 
-    let mut current = start;
-    let mut movement_points = *movement_points;
-    while current != goal {
-        // TODO: Limit pathfinding to partial knowledge:
-        // 1. Only tiles already explored by the current player would have a known
-        //    movement cost.
-        // 2. Only tiles already explored by the current player would have known
-        //    presence / absence of neighboring tiles. If the neighboring tile positions
-        //    have never been inside any unit's sight range, they must be assumed to
-        //    exist.
-        // 3. If there are any changes allowing / denying movement since the last seen
-        //    time, the changes must NOT be taken into consideration. Pathfinding must
-        //    be based on the last known map by the current player.
-
-        let shortest_path = astar(
-            &(current.x, current.y),
-            |&p| successors(p),
-            |&(x, y)| NotNan::from(CubePos::from(TilePos { x, y }).distance_from(&goal.into())),
-            |&(x, y)| TilePos { x, y } == goal,
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum MoveOutcome {
+    /// The unit has arrived at the goal.
+    Arrived,
+    /// The unit has to continue moving to the goal in the next turns.
+    OutOfMovementPoints,
+    /// There is no known path to the goal.
+    NoPath,
+}
+
+/// The terrain known to the player, for planning the movement of a unit.
+///
+/// Pathfinding is based on the last known map by the player, so that it doesn't
+/// reveal anything about the tiles which the player can't see.
+struct KnownTerrain<'a> {
+    player_visibility: &'a PlayerVisibility,
+    /// Whether the player's units can embark onto water tiles.
+    can_embark: bool,
+    /// The tiles seen by the unit along the way, which the player doesn't
+    /// remember yet as the unit hasn't actually moved.
+    revealed_tiles: HashMap<TilePos, Tile>,
+}
+
+impl KnownTerrain<'_> {
+    /// Returns the tile as known to the player, or `None` if the player has never
+    /// seen it.
+    fn get(&self, tile_pos: &TilePos) -> Option<&Tile> {
+        self.revealed_tiles
+            .get(tile_pos)
+            .or_else(|| self.player_visibility.remembered_tile(tile_pos))
+    }
+
+    /// Checks if the tile is known to be a water tile.
+    fn is_water(&self, tile_pos: &TilePos) -> bool {
+        self.get(tile_pos)
+            .is_some_and(|tile| tile.base_terrain.is_water())
+    }
+
+    /// Spends the movement points of the unit for a move at the movement cost,
+    /// returning the movement points spent, or `None` if there are not enough
+    /// movement points for the move.
+    ///
+    /// A unit with full movement points can always make a move, even if it costs
+    /// more than its full movement points. Embarking / disembarking costs all the
+    /// remaining movement points.
+    fn spend_movement_points(
+        &self,
+        unit: &Unit,
+        from_pos: &TilePos,
+        to_pos: &TilePos,
+        movement_cost: NotNan<f64>,
+        movement_points: &mut MovementPoints,
+    ) -> Option<NotNan<f64>> {
+        let is_embarked = self.is_water(from_pos);
+        let full_movement_points = unit.full_movement_points_for(is_embarked);
+        let movement_cost = if is_embarked != self.is_water(to_pos) {
+            if movement_points.0 == 0.0 {
+                return None;
+            }
+            movement_points.0
+        } else if movement_cost <= movement_points.0 {
+            movement_cost
+        } else if movement_points.0 == full_movement_points.0 {
+            movement_points.0
+        } else {
+            return None;
+        };
+        movement_points.0 -= movement_cost;
+        Some(movement_cost)
+    }
+
+    /// Reveals the tiles within the sight range of the unit at the tile.
+    fn reveal(&mut self, game_map: &GameMap, tile_pos: &TilePos, map_wrapping: MapWrapping) {
+        let map_size = &game_map.size();
+        self.revealed_tiles.extend(
+            positions_in_sight_range(tile_pos, UNIT_SIGHT_RANGE, map_size, map_wrapping)
+                .into_iter()
+                .map(|tile_pos| (tile_pos, game_map[&tile_pos])),
         );
+    }
 
-        if let Some((path, _total_movement_cost)) = shortest_path {
-            let next = path[1];
-            let movement_cost = successors(path[0])
-                .find_map(|(p, c)| if p == next { Some(c) } else { None })
-                .unwrap();
-            if movement_cost <= movement_points.0 {
-                movement_points.0 -= movement_cost;
-            } else if movement_points.0 == full_movement_points.0 {
-                movement_points.0 = NotNan::from(0);
-            } else {
-                // Not enough movement points.
-                // TODO: Queue movement for next turns.
-                break;
+    /// Plans the moves of the unit from the start towards the goal, for as far
+    /// as its movement points allow.
+    ///
+    /// The path is planned again whenever the unit reveals terrain contradicting
+    /// the planned path along the way.
+    fn plan_moves(
+        &mut self,
+        game_map: &GameMap,
+        map_wrapping: MapWrapping,
+        unit: &Unit,
+        start: TilePos,
+        goal: TilePos,
+        mut movement_points: MovementPoints,
+    ) -> (Vec<UnitMoved>, MoveOutcome) {
+        let map_size = &game_map.size();
+        let mut unit_moves = Vec::new();
+        let mut path = VecDeque::new();
+        let mut current = start;
+        while current != goal {
+            if !self.is_valid_path(&current, &path, map_size, map_wrapping) {
+                let Some(shortest_path) = self.find_path(&current, &goal, map_size, map_wrapping)
+                else {
+                    info!(?current, ?start, ?goal, "could not find path");
+                    return (unit_moves, MoveOutcome::NoPath);
+                };
+                path = shortest_path;
             }
-            let next = {
-                let (x, y) = next;
-                TilePos { x, y }
+
+            let &(next, movement_cost) = path.front().unwrap();
+            let Some(movement_cost) = self.spend_movement_points(
+                unit,
+                &current,
+                &next,
+                movement_cost,
+                &mut movement_points,
+            ) else {
+                // Not enough movement points.
+                return (unit_moves, MoveOutcome::OutOfMovementPoints);
             };
-            let unit_moved = UnitMoved {
-                unit_id,
+            path.pop_front();
+            unit_moves.push(UnitMoved {
+                unit_id: unit.unit_id,
                 from_pos: current,
                 to_pos: next,
                 movement_cost,
-            };
-            match multiplayer_state.get() {
-                MultiplayerState::Hosting => {
-                    unit_moved_events.write(unit_moved);
-                },
-                MultiplayerState::Joining => {
-                    request_events.write(unit_moved.into());
-                },
-                _ => {
-                    unreachable!("multiplayer state should not be inactive");
-                },
-            }
+            });
             current = next;
-        } else {
-            info!(?current, ?start, ?goal, "could not find path");
-            // TODO: Show indication that there is no path for this move.
-            break;
+            self.reveal(game_map, &current, map_wrapping);
         }
+
+        (unit_moves, MoveOutcome::Arrived)
+    }
+
+    /// Finds the shortest path from the start to the goal, as a sequence of
+    /// moves with their movement costs.
+    fn find_path(
+        &self,
+        start: &TilePos,
+        goal: &TilePos,
+        map_size: &TilemapSize,
+        map_wrapping: MapWrapping,
+    ) -> Option<VecDeque<(TilePos, NotNan<f64>)>> {
+        let (path, _total_movement_cost) = astar(
+            start,
+            |tile_pos| self.successors(tile_pos, map_size, map_wrapping),
+            |tile_pos| NotNan::from(hex_distance(tile_pos, goal, map_size, map_wrapping)),
+            |tile_pos| tile_pos == goal,
+        )?;
+
+        Some(
+            path.into_iter()
+                .tuple_windows()
+                .map(|(from_pos, to_pos)| {
+                    let movement_cost = self
+                        .successors(&from_pos, map_size, map_wrapping)
+                        .into_iter()
+                        .find_map(|(p, c)| if p == to_pos { Some(c) } else { None })
+                        .unwrap();
+                    (to_pos, movement_cost)
+                })
+                .collect(),
+        )
+    }
+
+    /// Checks if each move along the path is still possible at the same movement
+    /// cost.
+    fn is_valid_path(
+        &self,
+        start: &TilePos,
+        path: &VecDeque<(TilePos, NotNan<f64>)>,
+        map_size: &TilemapSize,
+        map_wrapping: MapWrapping,
+    ) -> bool {
+        if path.is_empty() {
+            return false;
+        }
+
+        let mut current = *start;
+        path.iter().all(|&(next, movement_cost)| {
+            let is_valid = self
+                .successors(&current, map_size, map_wrapping)
+                .contains(&(next, movement_cost));
+            current = next;
+            is_valid
+        })
+    }
+
+    /// Returns the neighboring tiles which a unit can move to from the tile,
+    /// along with the movement costs.
+    ///
+    /// The tiles which the player has never seen are assumed to exist, and to
+    /// be passable land at the default movement cost.
+    fn successors(
+        &self,
+        tile_pos: &TilePos,
+        map_size: &TilemapSize,
+        map_wrapping: MapWrapping,
+    ) -> Vec<(TilePos, NotNan<f64>)> {
+        // The size of the map is known to every player from the game setup, so the
+        // positions beyond the edges of the map are known not to exist.
+        let neighbor_positions = neighboring_positions(tile_pos, map_size, map_wrapping);
+        let current_tile = self.get(tile_pos);
+        let is_embarked = self.is_water(tile_pos);
+
+        HEX_DIRECTIONS
+            .into_iter()
+            .filter_map(|direction| {
+                let neighbor_pos = *neighbor_positions.get(direction)?;
+                let neighbor_tile = self.get(&neighbor_pos);
+                if let Some(tile) = neighbor_tile {
+                    if tile.base_terrain.is_mountains() {
+                        return None;
+                    }
+                    // Cliffs can't be scaled, so units can't embark / disembark across
+                    // them.
+                    if tile.cliff_hex_edges[(direction as usize + 3) % 6] {
+                        return None;
+                    }
+                }
+                if let Some(current_tile) = current_tile {
+                    if current_tile.cliff_hex_edges[direction as usize] {
+                        return None;
+                    }
+                }
+
+                match (is_embarked, self.is_water(&neighbor_pos)) {
+                    (true, true) => {
+                        // Embarked units move over water at the embarked speed.
+                        return Some((neighbor_pos, NotNan::from(EMBARKED_MOVEMENT_COST)));
+                    },
+                    (false, true) => {
+                        // Embarking.
+                        if !self.can_embark {
+                            return None;
+                        }
+                        return Some((neighbor_pos, NotNan::from(EMBARKATION_MOVEMENT_COST)));
+                    },
+                    (true, false) => {
+                        // Disembarking.
+                        return Some((neighbor_pos, NotNan::from(EMBARKATION_MOVEMENT_COST)));
+                    },
+                    (false, false) => {},
+                }
+
+                let movement_cost = match neighbor_tile {
+                    Some(tile) => {
+                        if tile.base_terrain.is_hills() {
+                            match tile.terrain_features {
+                                Some(TerrainFeatures::Woods | TerrainFeatures::Rainforest) => {
+                                    NotNan::from(3)
+                                },
+                                _ => NotNan::from(2),
+                            }
+                        } else {
+                            match tile.terrain_features {
+                                Some(
+                                    TerrainFeatures::Woods
+                                    | TerrainFeatures::Rainforest
+                                    | TerrainFeatures::Marsh,
+                                ) => NotNan::from(2),
+                                _ => NotNan::from(1),
+                            }
+                        }
+                    },
+                    None => NotNan::from(UNEXPLORED_MOVEMENT_COST),
+                };
+                let Some(current_tile) = current_tile else {
+                    return Some((neighbor_pos, movement_cost));
+                };
+                let movement_cost = if current_tile.river_hex_edges[direction as usize] {
+                    movement_cost + NotNan::from(3)
+                } else {
+                    movement_cost
+                };
+
+                Some((neighbor_pos, movement_cost))
+            })
+            .collect()
     }
 }
 
@@ -1237,7 +1774,7 @@ pub fn handle_unit_selected(
                         ),
                     );
                 }
-                if matches!(turn_state.get(), TurnState::InProgress) {
+                if matches!(turn_state.get(), TurnState::InProgress) && !unit_state.is_embarked() {
                     unit_actions_msg += "[F] Fortify\n";
                 }
             },
@@ -1300,6 +1837,7 @@ pub fn handle_unit_moved(
     mut commands: Commands,
     unit_entity_map: Res<UnitEntityMap>,
     multiplayer_state: Res<State<MultiplayerState>>,
+    game_map: Res<GameMap>,
     unit_selection_tilemap_query: Single<(&mut TileStorage,), UnitSelectionLayerFilter>,
     unit_state_tilemap_query: Single<(Entity, &mut TileStorage), UnitStateLayerFilter>,
     civilian_unit_tilemap_query: Single<(Entity, &mut TileStorage), CivilianUnitLayerFilter>,
@@ -1357,31 +1895,27 @@ pub fn handle_unit_moved(
 
         // Update unit.
         {
-            let (
-                _unit_entity,
-                _unit,
-                mut tile_pos,
-                _unit_type,
-                mut movement_points,
-                mut unit_state,
-            ) = unit_query.get_mut(*moved_unit_entity).unwrap();
+            let (_unit_entity, _unit, mut tile_pos, unit_type, mut movement_points, mut unit_state) =
+                unit_query.get_mut(*moved_unit_entity).unwrap();
             assert!(*tile_pos == from_pos);
             *tile_pos = to_pos;
             movement_points.0 -= movement_cost;
             if movement_points.0 == 0.0 {
                 let next_unit_state = match *unit_state {
-                    UnitState::CivilianReady | UnitState::CivilianReadyOutOfOrders => {
-                        UnitState::CivilianOutOfMoves
-                    },
-                    UnitState::LandMilitaryReady
-                    | UnitState::LandMilitaryReadyOutOfOrders
-                    | UnitState::LandMilitaryFortified
-                    | UnitState::LandMilitaryFortifiedOutOfOrders => {
-                        UnitState::LandMilitaryOutOfMoves
-                    },
-                    UnitState::CivilianOutOfMoves | UnitState::LandMilitaryOutOfMoves => {
+                    UnitState::CivilianOutOfMoves
+                    | UnitState::LandMilitaryOutOfMoves
+                    | UnitState::EmbarkedOutOfMoves => {
                         unreachable!("the unit being moved should not be out of moves");
                     },
+                    // Embarking / disembarking costs all the remaining movement points,
+                    // so the unit state only has to change when the unit is out of moves.
+                    _ if game_map[&to_pos].base_terrain.is_water() => UnitState::EmbarkedOutOfMoves,
+                    _ => match unit_type {
+                        UnitType::Civilian(_civilian_unit_type) => UnitState::CivilianOutOfMoves,
+                        UnitType::LandMilitary(_land_military_unit_type) => {
+                            UnitState::LandMilitaryOutOfMoves
+                        },
+                    },
                 };
                 *unit_state = next_unit_state;
             }
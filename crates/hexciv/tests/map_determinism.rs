@@ -0,0 +1,229 @@
+//! Regression tests for the determinism of the map generation.
+//!
+//! The joining peers regenerate the map from the map seed in the game setup,
+//! so the same game setup must generate an identical map on every platform,
+//! including with the float behavior of the wasm32 target.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::helpers::hex_grid::axial::AxialPos;
+use bevy_ecs_tilemap::helpers::hex_grid::offset::RowOddPos;
+use bitvec::prelude::*;
+use hexciv::game_setup::{MapScript, MapSize, MapWrapping};
+use hexciv::map::{GameMap, generate_headless_map};
+use hexciv::resource::{
+    BonusResource, MapBonusResources, MapStrategicResources, StrategicResource,
+};
+use hexciv::terrain::{BaseTerrain, River, StartingPositions, TerrainFeatures};
+use serde::Serialize;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+/// The stable hashes of the maps generated for each game setup.
+///
+/// If the map generation is changed on purpose, the hashes must be updated
+/// with the actual hashes reported by the failing test.
+const GOLDEN_MAP_HASHES: [(GoldenGameSetup, u64); 5] = [
+    (
+        GoldenGameSetup {
+            map_seed: 42,
+            num_players: 2,
+            map_size: MapSize::Duel,
+            map_script: MapScript::Pangaea,
+            map_wrapping: MapWrapping::WrapEastWest,
+        },
+        0xb559_c509_af48_eb4a,
+    ),
+    (
+        GoldenGameSetup {
+            map_seed: 7,
+            num_players: 2,
+            map_size: MapSize::Duel,
+            map_script: MapScript::Islands,
+            map_wrapping: MapWrapping::NoWrap,
+        },
+        0x1975_da62_808d_5214,
+    ),
+    (
+        GoldenGameSetup {
+            map_seed: 2024,
+            num_players: 3,
+            map_size: MapSize::Tiny,
+            map_script: MapScript::Continents,
+            map_wrapping: MapWrapping::WrapEastWest,
+        },
+        0x5513_5a2a_2df8_c9ed,
+    ),
+    (
+        GoldenGameSetup {
+            map_seed: 9_876_543_210,
+            num_players: 4,
+            map_size: MapSize::Tiny,
+            map_script: MapScript::Fractal,
+            map_wrapping: MapWrapping::NoWrap,
+        },
+        0x85fc_e886_74a2_ac6c,
+    ),
+    (SEAM_CROSSING_GAME_SETUP, 0xea7a_09cb_eeb8_6e58),
+];
+
+/// A game setup whose map wraps around east-west, with rivers crossing the
+/// seam between the east and west edges.
+const SEAM_CROSSING_GAME_SETUP: GoldenGameSetup = GoldenGameSetup {
+    map_seed: 2,
+    num_players: 2,
+    map_size: MapSize::Duel,
+    map_script: MapScript::Fractal,
+    map_wrapping: MapWrapping::WrapEastWest,
+};
+
+#[derive(Copy, Clone, Debug)]
+struct GoldenGameSetup {
+    map_seed: u64,
+    num_players: u8,
+    map_size: MapSize,
+    map_script: MapScript,
+    map_wrapping: MapWrapping,
+}
+
+/// The tile data which must be identical for every peer.
+#[derive(Serialize)]
+struct TileDigest {
+    base_terrain: BaseTerrain,
+    terrain_features: Option<TerrainFeatures>,
+    river_hex_edges: u32,
+    cliff_hex_edges: u32,
+    bonus_resource: Option<BonusResource>,
+    strategic_resource: Option<StrategicResource>,
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn generated_maps_match_golden_hashes() {
+    let mismatches: Vec<_> = GOLDEN_MAP_HASHES
+        .iter()
+        .filter_map(|&(game_setup, golden_hash)| {
+            let map_hash = generate_map_hash(game_setup);
+            (map_hash != golden_hash).then(|| {
+                format!("{game_setup:?}: expected {golden_hash:#018x}, got {map_hash:#018x}")
+            })
+        })
+        .collect();
+
+    assert!(
+        mismatches.is_empty(),
+        "generated maps diverged from the golden hashes:\n{}",
+        mismatches.join("\n")
+    );
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn rivers_crossing_the_seam_are_not_split() {
+    let GoldenGameSetup {
+        map_seed,
+        num_players,
+        map_size,
+        map_script,
+        map_wrapping,
+    } = SEAM_CROSSING_GAME_SETUP;
+    let mut app = generate_headless_map(map_seed, num_players, map_size, map_script, map_wrapping)
+        .expect("the map should have been generated");
+    let map_size = map_size.tilemap_size();
+
+    let world = app.world_mut();
+    let river_vertices: Vec<HashSet<_>> = world
+        .query::<&River>()
+        .iter(world)
+        .map(|river| {
+            river
+                .edges
+                .iter()
+                .flat_map(|river_edge| {
+                    [
+                        river_edge.upstream_vertex(&map_size, map_wrapping),
+                        river_edge.downstream_vertex(&map_size, map_wrapping),
+                    ]
+                })
+                .collect()
+        })
+        .collect();
+
+    let crosses_seam = |vertex: &[AxialPos; 3]| {
+        let columns: Vec<_> = vertex
+            .iter()
+            .map(|&axial_pos| RowOddPos::from(axial_pos).q)
+            .collect();
+        columns.contains(&0) && columns.contains(&(map_size.x as i32 - 1))
+    };
+    assert!(
+        river_vertices.iter().flatten().any(crosses_seam),
+        "the map should have rivers crossing the seam"
+    );
+
+    for (i, vertices) in river_vertices.iter().enumerate() {
+        for other_vertices in &river_vertices[i + 1..] {
+            assert!(
+                vertices.is_disjoint(other_vertices),
+                "rivers sharing a vertex should have been grouped into the same river"
+            );
+        }
+    }
+}
+
+/// Generates the map for the game setup, and returns a stable hash of its
+/// tile data.
+fn generate_map_hash(game_setup: GoldenGameSetup) -> u64 {
+    let GoldenGameSetup {
+        map_seed,
+        num_players,
+        map_size,
+        map_script,
+        map_wrapping,
+    } = game_setup;
+    let mut app = generate_headless_map(map_seed, num_players, map_size, map_script, map_wrapping)
+        .expect("the map should have been generated");
+
+    let tile_digests = app
+        .world_mut()
+        .run_system_cached(collect_tile_digests)
+        .expect("the resources should have been generated");
+    let starting_positions = &app.world().resource::<StartingPositions>().0;
+
+    let bytes = serde_json::to_vec(&(starting_positions, tile_digests))
+        .expect("serializing the tile data should not fail");
+    fnv1a_hash(&bytes)
+}
+
+fn collect_tile_digests(
+    game_map: Res<GameMap>,
+    map_bonus_resources: Res<MapBonusResources>,
+    map_strategic_resources: Res<MapStrategicResources>,
+) -> Vec<TileDigest> {
+    game_map
+        .iter()
+        .map(|(tile_pos, tile)| TileDigest {
+            base_terrain: tile.base_terrain,
+            terrain_features: tile.terrain_features,
+            river_hex_edges: tile.river_hex_edges.load(),
+            cliff_hex_edges: tile.cliff_hex_edges.load(),
+            bonus_resource: map_bonus_resources.get(&tile_pos),
+            strategic_resource: map_strategic_resources.get(&tile_pos),
+        })
+        .collect()
+}
+
+/// Hashes the bytes with 64-bit FNV-1a, which unlike the hashers in `std`, is
+/// stable across Rust versions and platforms.
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
@@ -0,0 +1,64 @@
+//! Tests for balancing the bonus resources in the starting area of each
+//! player.
+
+use hexciv::game_setup::{MapScript, MapSize, MapWrapping};
+use hexciv::map::{GameMap, generate_headless_map};
+use hexciv::resource::{
+    MapBonusResources, STARTING_AREA_MAX_BONUS_RESOURCES, STARTING_AREA_MIN_BONUS_RESOURCES,
+    STARTING_AREA_RADIUS,
+};
+use hexciv::terrain::{StartingPositions, hex_distance};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn every_starting_area_has_a_balanced_number_of_bonus_resources() {
+    for (map_seed, num_players, map_size, map_script, map_wrapping) in [
+        (
+            42,
+            2,
+            MapSize::Duel,
+            MapScript::Pangaea,
+            MapWrapping::WrapEastWest,
+        ),
+        (7, 2, MapSize::Duel, MapScript::Islands, MapWrapping::NoWrap),
+        (
+            2024,
+            3,
+            MapSize::Tiny,
+            MapScript::Continents,
+            MapWrapping::WrapEastWest,
+        ),
+    ] {
+        let app = generate_headless_map(map_seed, num_players, map_size, map_script, map_wrapping)
+            .expect("the map should have been generated");
+
+        let world = app.world();
+        let game_map = world.resource::<GameMap>();
+        let map_bonus_resources = world.resource::<MapBonusResources>();
+        let map_size = game_map.size();
+
+        for starting_position in &world.resource::<StartingPositions>().0 {
+            let num_bonus_resources = game_map
+                .iter()
+                .filter(|(tile_pos, _)| {
+                    hex_distance(tile_pos, starting_position, &map_size, map_wrapping)
+                        <= STARTING_AREA_RADIUS
+                        && map_bonus_resources.get(tile_pos).is_some()
+                })
+                .count();
+            assert!(
+                (STARTING_AREA_MIN_BONUS_RESOURCES..=STARTING_AREA_MAX_BONUS_RESOURCES)
+                    .contains(&num_bonus_resources),
+                "the starting area at {starting_position:?} with map seed {map_seed} should have \
+                 between {STARTING_AREA_MIN_BONUS_RESOURCES} and \
+                 {STARTING_AREA_MAX_BONUS_RESOURCES} bonus resources, but has \
+                 {num_bonus_resources}"
+            );
+        }
+    }
+}
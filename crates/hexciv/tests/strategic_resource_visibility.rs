@@ -0,0 +1,105 @@
+//! Tests for revealing the strategic resources to a player as their era
+//! advances.
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+use hexciv::civilization::Civilization;
+use hexciv::era::{Era, PlayerEra};
+use hexciv::game_setup::{MapScript, MapSize, MapWrapping};
+use hexciv::layer::ResourceLayerFilter;
+use hexciv::map::generate_headless_map;
+use hexciv::player::{OurPlayer, Player, PlayerIndex};
+use hexciv::resource::{
+    MapStrategicResources, StrategicResource, spawn_resource_tiles,
+    update_strategic_resource_visibility,
+};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn strategic_resources_are_revealed_as_the_era_advances() {
+    let mut app = generate_headless_map(
+        2024,
+        3,
+        MapSize::Tiny,
+        MapScript::Continents,
+        MapWrapping::WrapEastWest,
+    )
+    .expect("the map should have been generated");
+
+    let world = app.world_mut();
+    world
+        .run_system_cached(spawn_resource_tiles)
+        .expect("the resources should have been generated");
+    let player_entity = world
+        .spawn(Player {
+            player_index: PlayerIndex(0),
+            civ: Civilization::America,
+        })
+        .id();
+    world.insert_resource(OurPlayer(player_entity));
+
+    let mut hidden_in_ancient_era = 0;
+    for era in [
+        Era::Ancient,
+        Era::Classical,
+        Era::Medieval,
+        Era::Renaissance,
+        Era::Industrial,
+        Era::Modern,
+        Era::Atomic,
+        Era::Information,
+    ] {
+        let player_era = PlayerEra(era);
+        world.entity_mut(player_entity).insert(player_era);
+        world
+            .run_system_cached(update_strategic_resource_visibility)
+            .expect("the strategic resources should have been generated");
+
+        let strategic_resource_tiles = world
+            .run_system_cached(collect_strategic_resource_tiles)
+            .expect("the resource layer should have been spawned");
+        assert!(
+            !strategic_resource_tiles.is_empty(),
+            "the map should have strategic resources"
+        );
+        for (tile_pos, strategic_resource, visible) in strategic_resource_tiles {
+            assert_eq!(
+                visible,
+                strategic_resource.is_revealed_to(&player_era),
+                "{strategic_resource:?} at {tile_pos:?} should be shown only once revealed in the \
+                 {era:?} era"
+            );
+            if era == Era::Ancient && !visible {
+                hidden_in_ancient_era += 1;
+            } else if era == Era::Information {
+                assert!(
+                    visible,
+                    "every strategic resource should have been revealed"
+                );
+            }
+        }
+    }
+
+    assert!(
+        hidden_in_ancient_era > 0,
+        "the map should have strategic resources revealed after the ancient era"
+    );
+}
+
+fn collect_strategic_resource_tiles(
+    map_strategic_resources: Res<MapStrategicResources>,
+    resource_tile_query: Query<(&TilePos, &TileVisible), ResourceLayerFilter>,
+) -> Vec<(TilePos, StrategicResource, bool)> {
+    resource_tile_query
+        .iter()
+        .filter_map(|(tile_pos, tile_visible)| {
+            let strategic_resource = map_strategic_resources.get(tile_pos)?;
+            Some((*tile_pos, strategic_resource, tile_visible.0))
+        })
+        .collect()
+}